@@ -1,22 +1,35 @@
 use super::completer::LineReader;
-use crate::db::{Db, DbObject, Environment, InputOption, Request, Variable};
+use crate::cmd::run;
+use crate::db::secret::{self, SecretKey};
+use crate::db::{
+    Config, Db, DbObject, Environment, InputOption, Request, Setting, Variable, WorkflowStep,
+};
 use crate::error::{Error, ErrorKind, Result};
 use colored::*;
 use rusqlite::Connection;
+use std::env;
+use std::fs;
+use std::io::{self, Write};
 use std::path::PathBuf;
 
 pub struct Bastion {
     state: ReplState,
     db: Db,
     line_reader: LineReader,
+    interactive: bool,
+    key_file: Option<PathBuf>,
+    secret_key: Option<SecretKey>,
 }
 
 impl Bastion {
-    pub fn new(root: PathBuf) -> Result<Bastion> {
+    pub fn new(root: PathBuf, interactive: bool) -> Result<Bastion> {
         let mut bastion = Bastion {
             state: ReplState::Base(String::from("repost")),
             db: Db::new(&root, "repost.db")?,
             line_reader: LineReader::new(&root),
+            interactive,
+            key_file: None,
+            secret_key: None,
         };
         bastion.set_completions()?;
         bastion.set_options(InputOption::get_all(bastion.conn())?)?;
@@ -40,6 +53,10 @@ impl Bastion {
             })?);
         self.line_reader
             .workspace_completions(self.get_workspaces()?);
+        self.line_reader
+            .workflow_completions(WorkflowStep::collect_all(self.conn(), |x| {
+                String::from(x.workflow_name())
+            })?);
 
         let input_options = match &self.state {
             ReplState::Request(_, req) | ReplState::EnvironmentRequest(_, _, req) => {
@@ -90,16 +107,109 @@ impl Bastion {
 
     pub fn get_input(&mut self, input: &mut String) -> Option<()> {
         // read the line
-        self.line_reader.read_line(input, self.state.get_prompt())
+        let mut prompt = self.state.get_prompt();
+        if self.insecure() {
+            prompt = format!("{}{}", "[INSECURE]".on_red().white().bold(), prompt);
+        }
+        self.line_reader.read_line(input, prompt)
+    }
+    // TLS certificate verification is disabled in the current context
+    pub fn insecure(&self) -> bool {
+        match Config::resolve(
+            self.conn(),
+            self.current_environment(),
+            self.current_request(),
+        ) {
+            Ok(settings) => run::is_insecure(&settings),
+            Err(_) => false,
+        }
+    }
+
+    pub fn interactive(&self) -> bool {
+        self.interactive
+    }
+
+    pub fn set_key_file(&mut self, key_file: Option<PathBuf>) {
+        self.key_file = key_file;
+        self.secret_key = None;
+    }
+    // The key for secret variables of the current workspace. It comes from
+    // the key file, or else from REPOST_KEY_FILE, REPOST_PASSPHRASE, or a
+    // passphrase prompt on the terminal, and is kept for the rest of the session.
+    pub fn secret_key(&mut self) -> Result<&SecretKey> {
+        if self.secret_key.is_none() {
+            self.secret_key = Some(self.load_secret_key()?);
+        }
+        // We can unwrap because the key was just loaded
+        Ok(self.secret_key.as_ref().unwrap())
+    }
+    fn load_secret_key(&self) -> Result<SecretKey> {
+        let salt = match Setting::get_value(self.conn(), Setting::SECRET_SALT)? {
+            Some(x) => x,
+            None => secret::random_salt(),
+        };
+        let key_file = self
+            .key_file
+            .clone()
+            .or_else(|| env::var_os("REPOST_KEY_FILE").map(PathBuf::from));
+        let key = match (key_file, env::var("REPOST_PASSPHRASE")) {
+            (Some(path), _) => SecretKey::from_key_file(&fs::read(path)?),
+            (None, Ok(passphrase)) => SecretKey::from_passphrase(&passphrase, salt.as_bytes()),
+            (None, Err(_)) => {
+                let passphrase = rpassword::prompt_password("Passphrase for secret variables: ")
+                    .map_err(|_| {
+                        Error::new(ErrorKind::ArgumentError(
+                            "Secret variables need a key. Set REPOST_PASSPHRASE or REPOST_KEY_FILE.",
+                        ))
+                    })?;
+                SecretKey::from_passphrase(&passphrase, salt.as_bytes())
+            }
+        };
+
+        // the first key of a workspace is the only valid key from then on
+        match Setting::get_value(self.conn(), Setting::SECRET_CHECK)? {
+            Some(check) => {
+                key.decrypt(&check).map_err(|_| {
+                    Error::new(ErrorKind::ArgumentError("Wrong passphrase or key file."))
+                })?;
+            }
+            None => {
+                Setting::new(Setting::SECRET_SALT, &salt).upsert(self.conn())?;
+                Setting::new(Setting::SECRET_CHECK, &key.encrypt("repost")).upsert(self.conn())?;
+            }
+        }
+        Ok(key)
+    }
+    // Ask a yes or no question; the answer is always no when not interactive
+    pub fn confirm(&self, prompt: &str) -> Result<bool> {
+        if !self.interactive {
+            return Ok(false);
+        }
+        print!("{} [y/N]: ", prompt);
+        io::stdout().flush()?;
+        let mut input = String::new();
+        io::stdin().read_line(&mut input)?;
+        Ok(["y", "Y", "yes"].contains(&input.trim()))
     }
 
     pub fn execute(&mut self, command: &str) -> Result<()> {
         super::executer::execute(self, command)
     }
+    pub fn execute_args(&mut self, args: Vec<&str>) -> Result<()> {
+        super::executer::execute_args(self, args)
+    }
 
     pub fn state(&self) -> &ReplState {
         &self.state
     }
+    pub fn current_workspace(&self) -> &str {
+        match &self.state {
+            ReplState::Base(ws)
+            | ReplState::Environment(ws, _)
+            | ReplState::Request(ws, _)
+            | ReplState::EnvironmentRequest(ws, _, _) => ws.as_ref(),
+        }
+    }
     pub fn current_environment(&self) -> Option<&str> {
         match &self.state {
             ReplState::Environment(_, env) | ReplState::EnvironmentRequest(_, env, _) => {
@@ -125,6 +235,8 @@ impl Bastion {
         let ws = String::from(workspace);
 
         self.db.set_db(format!("{}.db", workspace).as_ref())?;
+        // every workspace has its own key
+        self.secret_key = None;
         self.state = match &self.state {
             ReplState::Base(_) => ReplState::Base(ws),
             ReplState::Environment(_, env) => {
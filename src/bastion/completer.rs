@@ -89,6 +89,9 @@ impl LineReader {
     pub fn workspace_completions(&mut self, ws: Vec<String>) {
         self.editor.helper_mut().unwrap().workspaces = ws;
     }
+    pub fn workflow_completions(&mut self, wfs: Vec<String>) {
+        self.editor.helper_mut().unwrap().workflows = wfs;
+    }
 }
 
 pub struct LineReaderHelper {
@@ -98,6 +101,7 @@ pub struct LineReaderHelper {
     variables: Vec<String>,
     input_options: Vec<String>,
     workspaces: Vec<String>,
+    workflows: Vec<String>,
 }
 impl LineReaderHelper {
     fn new(base_yaml: &Value) -> LineReaderHelper {
@@ -108,6 +112,7 @@ impl LineReaderHelper {
             variables: vec![],
             input_options: vec![],
             workspaces: vec![],
+            workflows: vec![],
         }
     }
 }
@@ -155,9 +160,35 @@ impl Completer for LineReaderHelper {
         if let Some(cmd) = cmd.get_child_mut(vec!["delete", "options"]) {
             cmd.completions = self.input_options.clone();
         }
-        if let Some(cmd) = cmd.get_child_mut(vec!["run"]) {
+        if let Some(cmd) = cmd.get_child_mut(vec!["show", "curl"]) {
+            cmd.completions = self.requests.clone();
+        }
+        if let Some(cmd) = cmd.get_child_mut(vec!["export", "curl"]) {
             cmd.completions = self.requests.clone();
         }
+        if let Some(cmd) = cmd.get_child_mut(vec!["show", "workflows"]) {
+            cmd.completions = self.workflows.clone();
+        }
+        if let Some(cmd) = cmd.get_child_mut(vec!["delete", "workflows"]) {
+            cmd.completions = self.workflows.clone();
+        }
+        if let Some(cmd) = cmd.get_child_mut(vec!["run", "workflow"]) {
+            cmd.completions = self.workflows.clone();
+        }
+        for action in ["create", "show", "delete"].iter() {
+            if let Some(cmd) = cmd.get_child_mut(vec![action, "oauth2"]) {
+                cmd.completions = self.environments.clone();
+            }
+        }
+        for auth in ["basic", "bearer", "apikey"].iter() {
+            if let Some(cmd) = cmd.get_child_mut(vec!["auth", auth]) {
+                cmd.completions = self.variables.clone();
+            }
+        }
+        if let Some(cmd) = cmd.get_child_mut(vec!["run"]) {
+            // keep the workflow subcommand
+            cmd.completions.extend(self.requests.clone());
+        }
         let mut cmd = &cmd;
         // split line
         let mut tokens = line.split_whitespace();
@@ -1,5 +1,8 @@
 use super::bastion::{Bastion, ReplState};
-use crate::cmd::{create, delete, extract, info, run, set, show};
+use crate::cmd::{
+    assert, auth, bench, create, delete, diff, edit, export, extract, import, info, run, set, show,
+    test,
+};
 use crate::error::{Error, ErrorKind, Result};
 use clap_v3::{load_yaml, App};
 
@@ -26,6 +29,8 @@ pub fn execute_args(b: &mut Bastion, args: Vec<&str>) -> Result<()> {
         ("create", Some(matches)) => match matches.subcommand() {
             ("request", Some(matches)) => create::request(b, matches),
             ("variable", Some(matches)) => create::variable(b, matches),
+            ("workflow", Some(matches)) => create::workflow(b, matches),
+            ("oauth2", Some(matches)) => create::oauth2(b, matches),
             _ => unreachable!(),
         },
         ("show", Some(matches)) => match matches.subcommand() {
@@ -37,7 +42,11 @@ pub fn execute_args(b: &mut Bastion, args: Vec<&str>) -> Result<()> {
                 show::print_table((String::from("workspace"), b.get_workspaces()?));
                 Ok(())
             }
+            ("workflows", Some(matches)) => show::workflows(b, matches),
+            ("config", Some(matches)) => show::config(b, matches),
+            ("oauth2", Some(matches)) => show::oauth2(b, matches),
             ("response", Some(matches)) => show::response(b, matches),
+            ("curl", Some(matches)) => export::curl(b, matches),
             _ => unreachable!(),
         },
         ("set", Some(matches)) => match matches.subcommand() {
@@ -58,16 +67,47 @@ pub fn execute_args(b: &mut Bastion, args: Vec<&str>) -> Result<()> {
                 matches.values_of("value").unwrap_or_default().collect(),
             ),
             ("variable", Some(matches)) => set::variable(b, matches),
+            ("retention", Some(matches)) => set::retention(b, matches),
+            ("config", Some(matches)) => set::config(b, matches),
             _ => unreachable!(),
         },
         ("delete", Some(matches)) => match matches.subcommand() {
             ("requests", Some(matches)) => delete::requests(b, matches),
             ("variables", Some(matches)) => delete::variables(b, matches),
+            ("workflows", Some(matches)) => delete::workflows(b, matches),
             ("options", Some(matches)) => delete::options(b, matches),
+            ("assertions", Some(matches)) => delete::assertions(b, matches),
+            ("config", Some(matches)) => delete::config(b, matches),
+            ("oauth2", Some(matches)) => delete::oauth2(b, matches),
             _ => unreachable!(),
         },
-        ("run", Some(matches)) => run::execute(b, matches, matches.value_of("request")),
+        ("diff", Some(matches)) => match matches.subcommand() {
+            ("response", Some(matches)) => diff::response(b, matches),
+            _ => unreachable!(),
+        },
+        ("export", Some(matches)) => match matches.subcommand() {
+            ("curl", Some(matches)) => export::curl(b, matches),
+            ("postman", Some(matches)) => export::postman(b, matches),
+            ("workspace", Some(matches)) => export::workspace(b, matches),
+            _ => unreachable!(),
+        },
+        ("import", Some(matches)) => match matches.subcommand() {
+            ("curl", Some(matches)) => import::curl(b, matches),
+            ("openapi", Some(matches)) => import::openapi(b, matches),
+            ("postman", Some(matches)) => import::postman(b, matches),
+            ("workspace", Some(matches)) => import::workspace(b, matches),
+            _ => unreachable!(),
+        },
+        ("run", Some(matches)) => match matches.subcommand() {
+            ("workflow", Some(matches)) => run::workflow(b, matches),
+            _ => run::execute(b, matches, matches.value_of("request")),
+        },
+        ("test", Some(matches)) => test::execute(b, matches),
+        ("bench", Some(matches)) => bench::execute(b, matches),
         ("extract", Some(matches)) => extract::execute(b, matches),
+        ("assert", Some(matches)) => assert::execute(b, matches),
+        ("auth", Some(matches)) => auth::execute(b, matches),
+        ("edit", Some(matches)) => edit::execute(b, matches),
         ("info", Some(matches)) => info::execute(b, matches),
         _ => Err(Error::new(ErrorKind::NotFound)),
     }
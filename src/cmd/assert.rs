@@ -0,0 +1,149 @@
+use crate::bastion::Bastion;
+use crate::db::{Assertion, DbObject};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use colored::*;
+use regex::Regex;
+use reqwest::header::HeaderMap;
+use reqwest::StatusCode;
+use serde_json::Value;
+use std::time::Duration;
+
+pub fn execute(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    if b.current_request().is_none() {
+        return Err(Error::new(ErrorKind::RequestStateExpected("Assert")));
+    }
+    let request = b.current_request().unwrap();
+    // We can unwrap because type and key are required
+    let typ = matches.value_of("type").unwrap();
+    let key = matches.value_of("key").unwrap();
+    let (comparison, expected) = match (matches.value_of("equals"), matches.value_of("matches")) {
+        (Some(x), _) => ("equals", Some(x)),
+        (_, Some(x)) => {
+            Regex::new(x)?;
+            ("matches", Some(x))
+        }
+        _ => ("exists", None),
+    };
+
+    let assertion = match typ {
+        "status" => {
+            if expected.is_some() {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Status assertions take the expected status as the key.",
+                )));
+            }
+            if key.len() != 3
+                || !key
+                    .chars()
+                    .all(|c| c.is_ascii_digit() || c == 'x' || c == 'X')
+            {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Expected a status code or class (e.g. 200 or 2xx).",
+                )));
+            }
+            Assertion::new(request, typ, "", "equals", Some(&key.to_lowercase()))
+        }
+        "time" => {
+            if expected.is_some() {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Time assertions take the maximum time in milliseconds as the key.",
+                )));
+            }
+            key.parse::<u64>()?;
+            Assertion::new(request, typ, "", "under", Some(&format!("{}ms", key)))
+        }
+        _ => Assertion::new(request, typ, key, comparison, expected),
+    };
+    assertion.create(b.conn())?;
+    Ok(())
+}
+
+// Check a response against an assertion. Returns the reason on failure.
+pub fn evaluate(
+    assertion: &Assertion,
+    status: StatusCode,
+    headers: &HeaderMap,
+    body: &str,
+    elapsed: Duration,
+) -> Option<String> {
+    let expected = assertion.expected().unwrap_or("");
+    match assertion.assertion_type() {
+        "status" => {
+            let matched = status
+                .as_str()
+                .chars()
+                .zip(expected.chars())
+                .all(|(s, e)| e == 'x' || s == e);
+            match matched && expected.len() == 3 {
+                true => None,
+                false => Some(format!("got {}", status.as_str())),
+            }
+        }
+        "time" => {
+            let max: u128 = expected.trim_end_matches("ms").parse().unwrap_or(0);
+            match elapsed.as_millis() < max {
+                true => None,
+                false => Some(format!("took {}ms", elapsed.as_millis())),
+            }
+        }
+        "header" => {
+            let values: Vec<String> = headers
+                .get_all(assertion.source())
+                .iter()
+                .map(|x| String::from_utf8_lossy(x.as_bytes()).into_owned())
+                .collect();
+            if values.is_empty() {
+                return Some(String::from("header not found"));
+            }
+            compare(assertion, values)
+        }
+        "body" => {
+            let root: Value = match serde_json::from_str(body) {
+                Ok(x) => x,
+                Err(_) => return Some(String::from("body is not JSON")),
+            };
+            let values = match super::run::get_json_values(&root, assertion.source()) {
+                Ok(x) if !x.is_empty() => x,
+                _ => return Some(String::from("path not found")),
+            };
+            // compare strings without their quotes
+            let values = values
+                .into_iter()
+                .map(|x| match x {
+                    Value::String(x) => x,
+                    x => x.to_string(),
+                })
+                .collect();
+            compare(assertion, values)
+        }
+        x => Some(format!("unknown assertion type {}", x)),
+    }
+}
+
+// All values must satisfy the comparison
+fn compare(assertion: &Assertion, values: Vec<String>) -> Option<String> {
+    let expected = assertion.expected().unwrap_or("");
+    let failed = match assertion.comparison() {
+        "exists" => None,
+        "equals" => values.iter().find(|x| *x != expected),
+        "matches" => match Regex::new(expected) {
+            Ok(re) => values.iter().find(|x| !re.is_match(x)),
+            Err(_) => return Some(String::from("invalid regex")),
+        },
+        x => return Some(format!("unknown comparison {}", x)),
+    };
+    failed.map(|x| format!("got '{}'", x))
+}
+
+pub fn print_result(description: &str, result: &str) {
+    match result {
+        "pass" => println!("{} {}", "[pass]".green(), description),
+        x => println!(
+            "{} {} ({})",
+            "[fail]".red(),
+            description,
+            x.trim_start_matches("fail: ")
+        ),
+    }
+}
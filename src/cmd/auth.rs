@@ -0,0 +1,48 @@
+use crate::bastion::Bastion;
+use crate::db::{Auth, DbObject, InputOption, Request};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+
+pub fn execute(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    if b.current_request().is_none() {
+        return Err(Error::new(ErrorKind::RequestStateExpected("Auth")));
+    }
+    let mut req = Request::get_by_name(b.conn(), b.current_request().unwrap())?.remove(0);
+
+    // credentials are read from variables through input options
+    let auth = match matches.subcommand() {
+        ("basic", Some(matches)) => Some(Auth::Basic(
+            placeholder(matches.value_of("username").unwrap()),
+            placeholder(matches.value_of("password").unwrap()),
+        )),
+        ("bearer", Some(matches)) => Some(Auth::Bearer(placeholder(
+            matches.value_of("token").unwrap(),
+        ))),
+        ("apikey", Some(matches)) => {
+            let key = placeholder(matches.value_of("key").unwrap());
+            match matches.value_of("query") {
+                Some(name) => Some(Auth::ApiKeyQuery(String::from(name), key)),
+                None => Some(Auth::ApiKeyHeader(
+                    String::from(matches.value_of("header").unwrap_or("X-API-Key")),
+                    key,
+                )),
+            }
+        }
+        ("oauth2", Some(_)) => Some(Auth::oauth2()),
+        ("none", Some(_)) => None,
+        _ => unreachable!(),
+    };
+    req.set_auth(auth);
+    req.update(b.conn())?;
+    b.set_options(InputOption::get_by_name(b.conn(), req.name())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+// Accept both `token` and `{token}`
+fn placeholder(variable: &str) -> String {
+    format!(
+        "{{{}}}",
+        variable.trim_start_matches('{').trim_end_matches('}')
+    )
+}
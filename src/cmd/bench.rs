@@ -0,0 +1,216 @@
+use crate::bastion::Bastion;
+use crate::db::{DbObject, PrintableTableStruct, Request};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use comfy_table::Cell;
+use reqwest::blocking;
+use std::collections::BTreeMap;
+use std::io;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+struct Sample {
+    // status code or a short description of the error
+    outcome: std::result::Result<String, String>,
+    elapsed: Duration,
+}
+
+struct Metric(&'static str, String);
+struct Outcome(String, usize);
+
+pub fn execute(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    let req = matches.value_of("request").or(b.current_request());
+    if req.is_none() {
+        return Err(Error::new(ErrorKind::NotFound));
+    }
+    let mut req = Request::get_by_name(b.conn(), req.unwrap())?;
+    if req.is_empty() {
+        return Err(Error::new(ErrorKind::NotFound));
+    }
+    let mut req = req.remove(0);
+    super::oauth2::authorize(b, &mut req)?;
+    if req.has_encrypted_options() {
+        req.decrypt_input_options(b.secret_key()?)?;
+    }
+    super::run::resolve_sources(b, &mut req)?;
+
+    let duration = match matches.value_of("duration") {
+        Some(x) => Some(super::cmd::parse_std_duration(x)?),
+        None => None,
+    };
+    // without a duration, send 100 requests by default
+    let limit = match (matches.value_of("requests"), duration) {
+        (Some(x), _) => Some(x.parse::<usize>()?),
+        (None, Some(_)) => None,
+        (None, None) => Some(100),
+    };
+    let concurrency = match matches.value_of("concurrency") {
+        Some(x) => x.parse::<usize>()?.max(1),
+        None => 1,
+    };
+
+    // cycle through all planned requests, expanding built-in placeholders
+    // such as {$uuid} for every request sent
+    let env = b.current_environment().map(String::from);
+    let client = super::run::create_client(b, env.as_deref(), Some(req.name()))?;
+    let templates = super::run::planned_requests(&req)?;
+    for template in templates.iter() {
+        build_reqwest(&client, template)?;
+    }
+
+    match (limit, duration) {
+        (Some(n), _) => println!(
+            "Sending {} requests to {} with {} workers..",
+            n,
+            req.name(),
+            concurrency
+        ),
+        (None, Some(d)) => println!(
+            "Sending requests to {} for {}s with {} workers..",
+            req.name(),
+            d.as_secs(),
+            concurrency
+        ),
+        _ => unreachable!(),
+    }
+
+    let start = Instant::now();
+    let deadline = duration.map(|d| start + d);
+    let sent = Arc::new(AtomicUsize::new(0));
+    let mut workers = vec![];
+    for _ in 0..concurrency {
+        let templates = templates.clone();
+        let (client, sent) = (client.clone(), sent.clone());
+        workers.push(thread::spawn(move || {
+            let mut samples = vec![];
+            loop {
+                if deadline.is_some_and(|d| Instant::now() >= d) {
+                    break;
+                }
+                let i = sent.fetch_add(1, Ordering::SeqCst);
+                if limit.is_some_and(|n| i >= n) {
+                    break;
+                }
+                let reqw = match build_reqwest(&client, &templates[i % templates.len()]) {
+                    Ok(x) => x,
+                    Err(_) => {
+                        samples.push(Sample {
+                            outcome: Err(String::from("invalid request")),
+                            elapsed: Duration::default(),
+                        });
+                        continue;
+                    }
+                };
+                let start = Instant::now();
+                let outcome = client
+                    .execute(reqw)
+                    .and_then(|mut resp| {
+                        resp.copy_to(&mut io::sink())?;
+                        Ok(resp.status())
+                    })
+                    .map(|status| status.to_string())
+                    .map_err(|x| {
+                        if x.is_timeout() {
+                            String::from("timeout")
+                        } else if x.is_connect() {
+                            String::from("connection error")
+                        } else {
+                            String::from("error")
+                        }
+                    });
+                samples.push(Sample {
+                    outcome,
+                    elapsed: start.elapsed(),
+                });
+            }
+            samples
+        }));
+    }
+    let mut samples = vec![];
+    for worker in workers {
+        samples.extend(worker.join().unwrap_or_default());
+    }
+    let total = start.elapsed();
+
+    print_summary(samples, total);
+    Ok(())
+}
+
+fn build_reqwest(client: &blocking::Client, template: &Request) -> Result<blocking::Request> {
+    let mut req = template.clone();
+    req.expand_placeholders()?;
+    super::run::create_reqwest(client, &mut req)
+}
+
+fn print_summary(samples: Vec<Sample>, total: Duration) {
+    let mut latencies: Vec<Duration> = samples
+        .iter()
+        .filter(|x| x.outcome.is_ok())
+        .map(|x| x.elapsed)
+        .collect();
+    latencies.sort();
+    let percentile = |p: f64| -> String {
+        if latencies.is_empty() {
+            return String::from("-");
+        }
+        let i = ((p * latencies.len() as f64).ceil() as usize).max(1) - 1;
+        format_duration(latencies[i])
+    };
+    let errors = samples.iter().filter(|x| x.outcome.is_err()).count();
+
+    let metrics = vec![
+        Metric("requests", samples.len().to_string()),
+        Metric("errors", errors.to_string()),
+        Metric("duration", format!("{:.2}s", total.as_secs_f64())),
+        Metric(
+            "throughput",
+            format!("{:.2} req/s", samples.len() as f64 / total.as_secs_f64()),
+        ),
+        Metric("p50", percentile(0.50)),
+        Metric("p90", percentile(0.90)),
+        Metric("p99", percentile(0.99)),
+        Metric("max", percentile(1.0)),
+    ];
+    println!("\n  Summary");
+    super::show::print_table(metrics);
+
+    let mut outcomes: BTreeMap<String, usize> = BTreeMap::new();
+    for sample in samples {
+        let outcome = match sample.outcome {
+            Ok(x) | Err(x) => x,
+        };
+        *outcomes.entry(outcome).or_default() += 1;
+    }
+    println!("\n  Responses");
+    super::show::print_table(
+        outcomes
+            .into_iter()
+            .map(|(x, count)| Outcome(x, count))
+            .collect::<Vec<_>>(),
+    );
+    println!();
+}
+
+fn format_duration(d: Duration) -> String {
+    format!("{:.2}ms", d.as_secs_f64() * 1000.0)
+}
+
+impl PrintableTableStruct for Metric {
+    fn get_header() -> Vec<Cell> {
+        vec![Cell::new("metric"), Cell::new("value")]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![Cell::new(self.0), Cell::new(&self.1)]]
+    }
+}
+
+impl PrintableTableStruct for Outcome {
+    fn get_header() -> Vec<Cell> {
+        vec![Cell::new("status"), Cell::new("count")]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![Cell::new(&self.0), Cell::new(self.1)]]
+    }
+}
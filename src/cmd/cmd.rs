@@ -1 +1,38 @@
+use crate::error::{Error, ErrorKind, Result};
+use chrono::Duration;
+use std::time;
 
+// parse a duration such as 500ms, 30s, 5m, 12h, or 7d (seconds by default)
+pub fn parse_duration(s: &str) -> Result<Duration> {
+    let split = s.find(|c: char| !c.is_ascii_digit()).unwrap_or(s.len());
+    let (num, unit) = s.split_at(split);
+    if num.is_empty() {
+        return Err(Error::new(ErrorKind::ParseError));
+    }
+    let num: i64 = num.parse()?;
+    match unit {
+        "ms" => Ok(Duration::milliseconds(num)),
+        "" | "s" => Ok(Duration::seconds(num)),
+        "m" => Ok(Duration::minutes(num)),
+        "h" => Ok(Duration::hours(num)),
+        "d" => Ok(Duration::days(num)),
+        _ => Err(Error::new(ErrorKind::ParseError)),
+    }
+}
+
+// parse_duration for APIs that take a std::time::Duration
+pub fn parse_std_duration(s: &str) -> Result<time::Duration> {
+    parse_duration(s)?
+        .to_std()
+        .map_err(|_| Error::new(ErrorKind::ArgumentError("Duration must be positive.")))
+}
+
+pub fn parse_bool(s: &str) -> Result<bool> {
+    match s {
+        "true" | "on" | "yes" => Ok(true),
+        "false" | "off" | "no" => Ok(false),
+        _ => Err(Error::new(ErrorKind::ArgumentError(
+            "Expected true or false.",
+        ))),
+    }
+}
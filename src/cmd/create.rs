@@ -1,5 +1,5 @@
 use crate::bastion::Bastion;
-use crate::db::{DbObject, InputOption, Request, Variable};
+use crate::db::{DbObject, InputOption, OAuth2, Request, Variable, WorkflowStep};
 use crate::error::{Error, ErrorKind, Result};
 use clap_v3::ArgMatches;
 use reqwest::Method;
@@ -37,9 +37,13 @@ pub fn request(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
             (items.next().unwrap().trim(), items.next().unwrap().trim())
         })
         .collect();
-    method = matches
-        .value_of("method")
-        .map(|x| Method::from_bytes(x.as_bytes()).unwrap_or(Method::GET));
+    method = match matches.value_of("method") {
+        Some(x) => Some(
+            Method::from_bytes(x.to_uppercase().as_bytes())
+                .map_err(|_| Error::new(ErrorKind::ArgumentError("Invalid HTTP method.")))?,
+        ),
+        None => None,
+    };
 
     let mut request = Request::new(name, method, url);
     for header in headers {
@@ -73,11 +77,94 @@ pub fn variable(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
         })
         .collect();
 
+    let secret = matches.is_present("secret");
     for env_val in env_vals {
         let (environment, value) = env_val;
-        Variable::new(name, &environment, Some(&value), Some("user")).create(b.conn())?;
+        let source = Variable::user_source(&value);
+        let mut var = Variable::new(name, &environment, Some(&value), Some(source));
+        // values read from env:, file:, or cmd: are not stored, so only what
+        // they resolve to is masked
+        match (secret, source) {
+            (true, Variable::USER) => var.encrypt(b.secret_key()?),
+            (true, _) => var.set_secret(true),
+            (false, _) => (),
+        }
+        var.create(b.conn())?;
     }
     b.set_options(InputOption::get_by(b.conn(), |x| x.option_name() == name)?)?;
     b.set_completions()?;
     Ok(())
 }
+
+pub fn workflow(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because name and steps are required
+    let name = matches.value_of("name").unwrap();
+    if WorkflowStep::exists(b.conn(), name)? {
+        return Err(Error::new(ErrorKind::ArgumentError(
+            "A workflow with that name already exists.",
+        )));
+    }
+
+    let mut workflow = vec![];
+    for (i, step) in matches.values_of("steps").unwrap().enumerate() {
+        let step = WorkflowStep::parse(name, i as u32 + 1, step)?;
+        if !Request::exists(b.conn(), step.request_name())? {
+            println!("Request '{}' not found.", step.request_name());
+            return Err(Error::new(ErrorKind::NotFound));
+        }
+        workflow.push(step);
+    }
+    for step in workflow {
+        step.create(b.conn())?;
+    }
+    b.set_completions()?;
+    Ok(())
+}
+
+pub fn oauth2(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because environment, token-url, and client-id are required
+    let environment = matches.value_of("environment").unwrap();
+    let grant_type = matches
+        .value_of("grant")
+        .unwrap_or(OAuth2::CLIENT_CREDENTIALS);
+    let username = matches.value_of("username");
+    let password = matches.value_of("password");
+    let refresh_token = matches.value_of("refresh-token");
+    match grant_type {
+        OAuth2::PASSWORD if username.is_none() || password.is_none() => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "The password grant requires --username and --password.",
+            )))
+        }
+        OAuth2::REFRESH_TOKEN if refresh_token.is_none() => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "The refresh_token grant requires --refresh-token.",
+            )))
+        }
+        _ => (),
+    }
+
+    let mut config = OAuth2::new(
+        environment,
+        matches.value_of("token-url").unwrap(),
+        grant_type,
+        matches.value_of("client-id").unwrap(),
+    );
+    config.set_client_secret(matches.value_of("client-secret"));
+    let scopes: Vec<&str> = matches.values_of("scope").unwrap_or_default().collect();
+    if !scopes.is_empty() {
+        config.set_scopes(Some(&scopes.join(" ")));
+    }
+    config.set_username(username);
+    config.set_password(password);
+    config.set_refresh_token(refresh_token);
+    config.upsert(b.conn())?;
+
+    // tokens from the previous configuration are no longer valid
+    super::oauth2::clear_tokens(b, environment)?;
+    b.set_options(InputOption::get_by(b.conn(), |x| {
+        x.option_name() == OAuth2::ACCESS_TOKEN_VARIABLE
+    })?)?;
+    b.set_completions()?;
+    Ok(())
+}
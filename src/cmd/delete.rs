@@ -1,5 +1,7 @@
 use crate::bastion::Bastion;
-use crate::db::{DbObject, InputOption, OutputOption, Request, Variable};
+use crate::db::{
+    Assertion, Config, DbObject, InputOption, OAuth2, OutputOption, Request, Variable, WorkflowStep,
+};
 use crate::error::Result;
 use clap_v3::ArgMatches;
 
@@ -21,6 +23,40 @@ pub fn requests(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     Ok(())
 }
 
+pub fn workflows(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    let workflows: Vec<&str> = matches.values_of("workflow").unwrap().collect();
+    for workflow in workflows {
+        let v = WorkflowStep::get_by_name(b.conn(), workflow)?;
+        if v.is_empty() {
+            println!("Workflow '{}' not found.", workflow);
+            continue;
+        }
+        for e in v {
+            e.delete(b.conn())?;
+        }
+    }
+    b.set_completions()?;
+    Ok(())
+}
+
+pub fn config(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    let environment = matches.value_of("environment");
+    let request = matches.value_of("request");
+    for key in matches.values_of("key").unwrap() {
+        let v = Config::get_by(b.conn(), |x| {
+            x.key() == key && x.environment() == environment && x.request_name() == request
+        })?;
+        if v.is_empty() {
+            println!("Setting '{}' not found.", key);
+            continue;
+        }
+        for e in v {
+            e.delete(b.conn())?;
+        }
+    }
+    Ok(())
+}
+
 pub fn variables(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     let vars: Vec<&str> = matches.values_of("variable").unwrap().collect();
     for var in vars {
@@ -63,3 +99,39 @@ pub fn options(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     b.set_completions()?;
     Ok(())
 }
+
+pub fn assertions(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    let req = b.current_request().unwrap();
+    let ids: Vec<&str> = matches.values_of("id").unwrap().collect();
+    for id in ids {
+        let id: u32 = id.parse()?;
+        let v = Assertion::get_by(b.conn(), |x| x.id() == id && x.request_name() == req)?;
+        if v.is_empty() {
+            println!("Assertion '{}' not found.", id);
+            continue;
+        }
+        for e in v {
+            e.delete(b.conn())?;
+        }
+    }
+    Ok(())
+}
+
+pub fn oauth2(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    for environment in matches.values_of("environment").unwrap() {
+        let v = OAuth2::get_by_name(b.conn(), environment)?;
+        if v.is_empty() {
+            println!("OAuth2 configuration for '{}' not found.", environment);
+            continue;
+        }
+        for e in v {
+            e.delete(b.conn())?;
+        }
+        super::oauth2::clear_tokens(b, environment)?;
+    }
+    b.set_options(InputOption::get_by(b.conn(), |x| {
+        x.option_name() == OAuth2::ACCESS_TOKEN_VARIABLE
+    })?)?;
+    b.set_completions()?;
+    Ok(())
+}
@@ -0,0 +1,380 @@
+use crate::bastion::Bastion;
+use crate::db::RequestResponse;
+use crate::error::Result;
+use clap_v3::ArgMatches;
+use colored::*;
+use serde_json::Value;
+use similar::{ChangeTag, TextDiff};
+use std::collections::BTreeMap;
+
+#[derive(Debug, PartialEq)]
+enum Change {
+    Added(String, String),
+    Removed(String, String),
+    Changed(String, String, String),
+}
+
+pub fn response(b: &Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because both ids are required
+    let old = RequestResponse::get_by_id(b.conn(), matches.value_of("id1").unwrap().parse()?)?;
+    let new = RequestResponse::get_by_id(b.conn(), matches.value_of("id2").unwrap().parse()?)?;
+
+    println!("\n{}", "  Request".bold());
+    println!("  =========");
+    let old_line = format!("{} {}", old.method(), old.url());
+    let new_line = format!("{} {}", new.method(), new.url());
+    if old_line != new_line {
+        print_changes(vec![Change::Changed(
+            String::from("request"),
+            old_line,
+            new_line,
+        )]);
+    }
+    print_section(
+        "Input Options",
+        pair_changes(old.input_options(), new.input_options()),
+    );
+    print_section(
+        "Request Headers",
+        pair_changes(
+            split_headers(old.request_headers()),
+            split_headers(new.request_headers()),
+        ),
+    );
+    print_body_diff("Request Body", old.request_body(), new.request_body());
+
+    println!("\n{}", "  Response".bold());
+    println!("  ==========");
+    let (old_status, new_status) = (old.status().unwrap_or("-"), new.status().unwrap_or("-"));
+    if old_status != new_status {
+        print_changes(vec![Change::Changed(
+            String::from("status"),
+            String::from(old_status),
+            String::from(new_status),
+        )]);
+    }
+    print_section(
+        "Response Headers",
+        pair_changes(
+            split_headers(old.response_headers()),
+            split_headers(new.response_headers()),
+        ),
+    );
+    print_body_diff("Response Body", old.response_body(), new.response_body());
+    println!();
+    Ok(())
+}
+
+fn split_headers(headers: Vec<&str>) -> Vec<(&str, &str)> {
+    headers
+        .into_iter()
+        .filter_map(|h| {
+            let mut items = h.splitn(2, ':');
+            Some((items.next()?.trim(), items.next()?.trim()))
+        })
+        .collect()
+}
+
+fn pair_changes(old: Vec<(&str, &str)>, new: Vec<(&str, &str)>) -> Vec<Change> {
+    // header names are case insensitive and may be repeated, e.g. Set-Cookie
+    let to_map = |v: Vec<(&str, &str)>| -> BTreeMap<String, Vec<String>> {
+        let mut map: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for (k, v) in v {
+            map.entry(k.to_lowercase())
+                .or_default()
+                .push(String::from(v));
+        }
+        map
+    };
+    let (old, new) = (to_map(old), to_map(new));
+    let empty = vec![];
+    let mut changes = vec![];
+    for (key, old_values) in old.iter() {
+        let new_values = new.get(key).unwrap_or(&empty);
+        if let ([old_value], [new_value]) = (old_values.as_slice(), new_values.as_slice()) {
+            if old_value != new_value {
+                changes.push(Change::Changed(
+                    key.clone(),
+                    old_value.clone(),
+                    new_value.clone(),
+                ));
+            }
+            continue;
+        }
+        // compare repeated values as multisets
+        let mut added = new_values.clone();
+        for value in old_values {
+            match added.iter().position(|x| x == value) {
+                Some(i) => {
+                    added.remove(i);
+                }
+                None => changes.push(Change::Removed(key.clone(), value.clone())),
+            }
+        }
+        for value in added {
+            changes.push(Change::Added(key.clone(), value));
+        }
+    }
+    for (key, values) in new.iter() {
+        if !old.contains_key(key) {
+            for value in values {
+                changes.push(Change::Added(key.clone(), value.clone()));
+            }
+        }
+    }
+    changes
+}
+
+fn print_section(title: &str, changes: Vec<Change>) {
+    if changes.is_empty() {
+        return;
+    }
+    println!("\n  {}", title);
+    print_changes(changes);
+}
+
+fn print_changes(changes: Vec<Change>) {
+    for change in changes {
+        match change {
+            Change::Added(key, value) => println!("{}", format!("+ {}: {}", key, value).green()),
+            Change::Removed(key, value) => println!("{}", format!("- {}: {}", key, value).red()),
+            Change::Changed(key, old, new) => {
+                println!("{}", format!("~ {}: {} -> {}", key, old, new).yellow())
+            }
+        }
+    }
+}
+
+fn print_body_diff(title: &str, old: Option<&Vec<u8>>, new: Option<&Vec<u8>>) {
+    let old = String::from_utf8_lossy(old.map(|x| x.as_ref()).unwrap_or(&[])).into_owned();
+    let new = String::from_utf8_lossy(new.map(|x| x.as_ref()).unwrap_or(&[])).into_owned();
+    if old == new {
+        return;
+    }
+
+    // compare JSON structurally, otherwise fall back to a line diff
+    let json: (serde_json::Result<Value>, serde_json::Result<Value>) =
+        (serde_json::from_str(&old), serde_json::from_str(&new));
+    if let (Ok(old), Ok(new)) = json {
+        let mut changes = vec![];
+        json_changes(String::new(), &old, &new, &mut changes);
+        print_section(title, changes);
+        return;
+    }
+    println!("\n  {}", title);
+    for line in unified_diff(&old, &new, 3) {
+        match line.chars().next() {
+            Some('+') => println!("{}", line.green()),
+            Some('-') => println!("{}", line.red()),
+            Some('@') => println!("{}", line.cyan()),
+            _ => println!("{}", line),
+        }
+    }
+}
+
+fn json_changes(path: String, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+    let display = |path: &str| match path.len() {
+        0 => String::from("."),
+        _ => String::from(path),
+    };
+    match (old, new) {
+        (Value::Object(old), Value::Object(new)) => {
+            for (key, value) in old.iter() {
+                let path = match path.len() {
+                    0 => key.clone(),
+                    _ => format!("{}.{}", path, key),
+                };
+                match new.get(key) {
+                    Some(x) => json_changes(path, value, x, changes),
+                    None => changes.push(Change::Removed(path, value.to_string())),
+                }
+            }
+            for (key, value) in new.iter() {
+                if !old.contains_key(key) {
+                    let path = match path.len() {
+                        0 => key.clone(),
+                        _ => format!("{}.{}", path, key),
+                    };
+                    changes.push(Change::Added(path, value.to_string()));
+                }
+            }
+        }
+        (Value::Array(old), Value::Array(new)) => {
+            for i in 0..old.len().max(new.len()) {
+                let path = format!("{}[{}]", path, i);
+                match (old.get(i), new.get(i)) {
+                    (Some(x), Some(y)) => json_changes(path, x, y, changes),
+                    (Some(x), None) => changes.push(Change::Removed(path, x.to_string())),
+                    (None, Some(y)) => changes.push(Change::Added(path, y.to_string())),
+                    (None, None) => (),
+                }
+            }
+        }
+        (old, new) if old != new => changes.push(Change::Changed(
+            display(&path),
+            old.to_string(),
+            new.to_string(),
+        )),
+        _ => (),
+    }
+}
+
+fn unified_diff(old: &str, new: &str, context: usize) -> Vec<String> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    // Myers' diff keeps memory linear in the number of lines
+    let diff = TextDiff::from_slices(&old, &new);
+    let mut lines = vec![];
+    for hunk in diff.grouped_ops(context) {
+        let (first, last) = (&hunk[0], &hunk[hunk.len() - 1]);
+        let (old_start, new_start) = (first.old_range().start, first.new_range().start);
+        lines.push(format!(
+            "@@ -{},{} +{},{} @@",
+            old_start + 1,
+            last.old_range().end - old_start,
+            new_start + 1,
+            last.new_range().end - new_start
+        ));
+        for op in hunk.iter() {
+            for change in diff.iter_changes(op) {
+                let prefix = match change.tag() {
+                    ChangeTag::Equal => ' ',
+                    ChangeTag::Delete => '-',
+                    ChangeTag::Insert => '+',
+                };
+                lines.push(format!("{}{}", prefix, change.value()));
+            }
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn numbered(lines: std::ops::Range<usize>) -> Vec<String> {
+        lines.map(|i| format!("line {}", i)).collect()
+    }
+
+    #[test]
+    fn unified_diff_hunk_headers() {
+        let old = numbered(1..11).join("\n");
+        let mut new = numbered(1..11);
+        new[4] = String::from("changed");
+        let new = new.join("\n");
+        assert_eq!(
+            unified_diff(&old, &new, 2),
+            vec![
+                "@@ -3,5 +3,5 @@",
+                " line 3",
+                " line 4",
+                "-line 5",
+                "+changed",
+                " line 6",
+                " line 7",
+            ]
+        );
+        assert!(unified_diff(&old, &old, 2).is_empty());
+    }
+
+    #[test]
+    fn unified_diff_merges_close_changes() {
+        let old = numbered(1..21);
+        let mut close = old.clone();
+        close[4] = String::from("a");
+        close[7] = String::from("b");
+        let diff = unified_diff(&old.join("\n"), &close.join("\n"), 2);
+        assert_eq!(diff.iter().filter(|x| x.starts_with("@@")).count(), 1);
+        assert_eq!(diff[0], "@@ -3,8 +3,8 @@");
+
+        let mut far = old.clone();
+        far[2] = String::from("a");
+        far[15] = String::from("b");
+        let diff = unified_diff(&old.join("\n"), &far.join("\n"), 2);
+        let headers: Vec<_> = diff.iter().filter(|x| x.starts_with("@@")).collect();
+        assert_eq!(headers, vec!["@@ -1,5 +1,5 @@", "@@ -14,5 +14,5 @@"]);
+    }
+
+    #[test]
+    fn unified_diff_added_and_removed_lines() {
+        assert_eq!(
+            unified_diff("a\nb\n", "a\nb\nc\n", 3),
+            vec!["@@ -1,2 +1,3 @@", " a", " b", "+c"]
+        );
+        assert_eq!(
+            unified_diff("a\nb\nc\n", "b\nc\n", 3),
+            vec!["@@ -1,3 +1,2 @@", "-a", " b", " c"]
+        );
+    }
+
+    #[test]
+    fn unified_diff_large_bodies() {
+        let old = numbered(0..20000).join("\n");
+        let new = numbered(1..20001).join("\n");
+        let diff = unified_diff(&old, &new, 3);
+        assert_eq!(diff.iter().filter(|x| x.starts_with('-')).count(), 1);
+        assert_eq!(diff.iter().filter(|x| x.starts_with('+')).count(), 1);
+    }
+
+    #[test]
+    fn json_changes_paths() {
+        let old: Value = serde_json::from_str(r#"{"a":1,"b":{"c":[1,2]},"d":"x"}"#).unwrap();
+        let new: Value = serde_json::from_str(r#"{"a":2,"b":{"c":[1]},"e":true}"#).unwrap();
+        let mut changes = vec![];
+        json_changes(String::new(), &old, &new, &mut changes);
+        assert_eq!(
+            changes,
+            vec![
+                Change::Changed(String::from("a"), String::from("1"), String::from("2")),
+                Change::Removed(String::from("b.c[1]"), String::from("2")),
+                Change::Removed(String::from("d"), String::from("\"x\"")),
+                Change::Added(String::from("e"), String::from("true")),
+            ]
+        );
+
+        let mut changes = vec![];
+        json_changes(
+            String::new(),
+            &Value::from(1),
+            &Value::from("1"),
+            &mut changes,
+        );
+        assert_eq!(
+            changes,
+            vec![Change::Changed(
+                String::from("."),
+                String::from("1"),
+                String::from("\"1\"")
+            )]
+        );
+    }
+
+    #[test]
+    fn pair_changes_repeated_headers() {
+        let old = vec![
+            ("Set-Cookie", "a=1"),
+            ("set-cookie", "b=2"),
+            ("Vary", "Accept"),
+        ];
+        let new = vec![
+            ("Set-Cookie", "a=1"),
+            ("Set-Cookie", "b=3"),
+            ("Vary", "Origin"),
+            ("ETag", "x"),
+        ];
+        assert_eq!(
+            pair_changes(old, new),
+            vec![
+                Change::Removed(String::from("set-cookie"), String::from("b=2")),
+                Change::Added(String::from("set-cookie"), String::from("b=3")),
+                Change::Changed(
+                    String::from("vary"),
+                    String::from("Accept"),
+                    String::from("Origin")
+                ),
+                Change::Added(String::from("etag"), String::from("x")),
+            ]
+        );
+    }
+}
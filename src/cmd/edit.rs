@@ -0,0 +1,122 @@
+use crate::bastion::Bastion;
+use crate::db::workspace_file::RequestEntry;
+use crate::db::{Assertion, DbObject, InputOption, Request};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use reqwest::Method;
+use std::env;
+use std::fs;
+use std::io::Write;
+use std::process::Command;
+
+pub fn execute(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    if b.current_request().is_none() {
+        return Err(Error::new(ErrorKind::RequestStateExpected("Edit")));
+    }
+    let mut req = Request::get_by_name(b.conn(), b.current_request().unwrap())?.remove(0);
+
+    if matches.is_present("editor") {
+        req = edit_with_editor(b, &req)?;
+    } else {
+        // TODO: add validator to yaml once available
+        let headers = matches.values_of("add-header").unwrap_or_default();
+        if !headers.clone().all(|s| s.contains(':')) {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "Found argument that does not contain ':'",
+            )));
+        }
+
+        if let Some(url) = matches.value_of("url") {
+            req.set_url(url);
+        }
+        if let Some(method) = matches.value_of("method") {
+            req.set_method(
+                Method::from_bytes(method.to_uppercase().as_bytes())
+                    .map_err(|_| Error::new(ErrorKind::ArgumentError("Invalid HTTP method.")))?,
+            );
+        }
+        for header in matches.values_of("remove-header").unwrap_or_default() {
+            req.remove_header(header);
+        }
+        for header in headers {
+            let mut items = header.splitn(2, ':');
+            // We can unwrap because this argument is guaranteed to have one ':'
+            let (key, value) = (items.next().unwrap().trim(), items.next().unwrap().trim());
+            // replace the header if it already exists
+            req.remove_header(key);
+            req.add_header(key, value);
+        }
+        if let Some(combination) = matches.value_of("combine") {
+            req.set_combination(Some(combination));
+        }
+        if let Some(data) = matches.value_of("data") {
+            // an empty argument removes the body
+            let body = if data.is_empty() {
+                None
+            } else if data.starts_with('@') {
+                let mut filename = data.chars();
+                filename.next(); // discard @
+                Some(fs::read(filename.collect::<String>())?)
+            } else {
+                Some(data.as_bytes().to_vec())
+            };
+            req.set_body(body);
+        }
+    }
+
+    req.update(b.conn())?;
+    b.set_options(InputOption::get_by_name(b.conn(), req.name())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+fn edit_with_editor(b: &Bastion, req: &Request) -> Result<Request> {
+    let assertions = Assertion::get_by_name(b.conn(), req.name())?;
+    let mut entry = RequestEntry::from(req);
+    entry.set_assertions(&assertions);
+    // the file is created with a random name and only readable by the user,
+    // and removed when it goes out of scope
+    let mut file = tempfile::Builder::new()
+        .prefix("repost-")
+        .suffix(".yaml")
+        .tempfile()?;
+    file.write_all(serde_yaml::to_string(&entry)?.as_bytes())?;
+    file.flush()?;
+    let path = file.path();
+
+    // TODO: support args in $EDITOR
+    let editor = env::var("VISUAL")
+        .or(env::var("EDITOR"))
+        .unwrap_or(String::from("vi"));
+    let status = Command::new(editor).arg(path).status();
+    let contents = fs::read_to_string(path);
+    drop(file);
+    if !status?.success() {
+        return Err(Error::new(ErrorKind::ArgumentError(
+            "Editor exited with an error, discarding changes.",
+        )));
+    }
+
+    let entry: RequestEntry = serde_yaml::from_str(&contents?)?;
+    if entry.name() != req.name() {
+        return Err(Error::new(ErrorKind::ArgumentError(
+            "Renaming a request is not supported.",
+        )));
+    }
+    let new_req = entry.to_request()?;
+
+    // replace output options and assertions with the edited ones
+    for opt in req.output_options() {
+        opt.delete(b.conn())?;
+    }
+    for opt in entry.output_options(req.name()) {
+        opt.create(b.conn())?;
+    }
+    for assertion in assertions {
+        assertion.delete(b.conn())?;
+    }
+    for assertion in entry.assertions(req.name()) {
+        assertion.create(b.conn())?;
+    }
+    Ok(new_req)
+}
@@ -0,0 +1,240 @@
+use crate::bastion::Bastion;
+use crate::db::{
+    DbObject, Environment, InputOption, OutputOption, Request, Variable, WorkspaceFile,
+};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use regex::Regex;
+use reqwest::blocking;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+
+pub fn curl(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    let req = matches.value_of("request").or(b.current_request());
+    if req.is_none() {
+        return Err(Error::new(ErrorKind::RequestStateExpected("Export curl")));
+    }
+    let mut req = Request::get_by_name(b.conn(), req.unwrap())?;
+    if req.is_empty() {
+        return Err(Error::new(ErrorKind::NotFound));
+    }
+    let mut req = req.remove(0);
+    req.mask_input_options();
+
+    // the client only applies the auth, so its settings are not needed
+    let client = blocking::Client::new();
+    for req in super::run::create_requests(&req)? {
+        let mut headers: Vec<String> = match req.headers() {
+            Some(headers) => headers.split('\n').map(String::from).collect(),
+            None => vec![],
+        };
+        // build the request as it is sent to pick up the auth header or
+        // query parameter
+        let reqw = super::run::create_reqwest(&client, &mut req.clone())?;
+        for (name, value) in reqw.headers() {
+            let exists = headers.iter().any(|x| {
+                let key = x.split(':').next().unwrap_or("");
+                key.trim().eq_ignore_ascii_case(name.as_str())
+            });
+            if !exists {
+                headers.push(format!("{}: {}", name, value.to_str().unwrap_or("")));
+            }
+        }
+        println!(
+            "{}",
+            curl_command(
+                req.method().as_str(),
+                reqw.url().as_str(),
+                headers.iter().map(AsRef::as_ref).collect(),
+                req.body().as_ref().map(|x| x.as_ref())
+            )
+        );
+    }
+    Ok(())
+}
+
+pub fn workspace(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because file is required
+    let filename = matches.value_of("file").unwrap();
+    let file = WorkspaceFile::from_db(b.conn())?;
+    fs::write(filename, serde_yaml::to_string(&file)?)?;
+    Ok(())
+}
+
+pub fn postman(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because file is required
+    let filename = matches.value_of("file").unwrap();
+    let items: Vec<Value> = Request::get_all(b.conn())?
+        .iter()
+        .map(postman_item)
+        .collect();
+    // collection variables are taken from the current environment
+    let variables = match b.current_environment() {
+        Some(env) => postman_variables(b, env)?,
+        None => vec![],
+    };
+    let collection = json!({
+        "info": {
+            "name": b.current_workspace(),
+            "schema": "https://schema.getpostman.com/json/collection/v2.1.0/collection.json",
+        },
+        "item": items,
+        "variable": variables,
+    });
+    fs::write(filename, serde_json::to_string_pretty(&collection)?)?;
+
+    if matches.is_present("environments") {
+        let path = Path::new(filename);
+        let stem = path
+            .file_stem()
+            .and_then(|x| x.to_str())
+            .unwrap_or("repost");
+        for env in Environment::get_all(b.conn())? {
+            let environment = json!({
+                "name": env.name(),
+                "values": postman_variables(b, env.name())?,
+                "_postman_variable_scope": "environment",
+            });
+            let filename =
+                path.with_file_name(format!("{}.{}.postman_environment.json", stem, env.name()));
+            fs::write(filename, serde_json::to_string_pretty(&environment)?)?;
+        }
+    }
+    Ok(())
+}
+
+fn postman_item(req: &Request) -> Value {
+    let headers: Vec<Value> = match req.headers() {
+        Some(headers) => headers
+            .split('\n')
+            .filter_map(|h| {
+                let mut items = h.splitn(2, ':');
+                let (key, value) = (items.next()?, items.next()?);
+                Some(json!({
+                    "key": key.trim(),
+                    "value": to_postman_placeholders(value.trim()),
+                }))
+            })
+            .collect(),
+        None => vec![],
+    };
+    let mut request = json!({
+        "method": req.method().as_str(),
+        "header": headers,
+        "url": { "raw": to_postman_placeholders(req.url()) },
+    });
+    if let Some(body) = req.body() {
+        request["body"] = json!({
+            "mode": "raw",
+            "raw": to_postman_placeholders(&String::from_utf8_lossy(body)),
+        });
+    }
+
+    let mut item = json!({ "name": req.name(), "request": request });
+    if !req.output_options().is_empty() {
+        item["event"] = json!([{
+            "listen": "test",
+            "script": {
+                "type": "text/javascript",
+                "exec": postman_script(req.output_options()),
+            },
+        }]);
+    }
+    item
+}
+
+fn postman_script(output_opts: &Vec<OutputOption>) -> Vec<String> {
+    let mut exec = vec![];
+    for opt in output_opts {
+        // the comment is used to restore the extraction on import
+        exec.push(format!(
+            "// repost: extract {} {} --to-var {}",
+            opt.extraction_type(),
+            opt.extraction_source(),
+            opt.option_name()
+        ));
+        let value = match opt.extraction_type() {
+            "header" => format!("pm.response.headers.get({:?})", opt.extraction_source()),
+            // javascript cannot express extracting every item of an array
+            _ if opt.extraction_source().contains("[*]") => continue,
+            _ => {
+                let path: String = opt
+                    .extraction_source()
+                    .split('.')
+                    .map(|token| {
+                        let mut items = token.splitn(2, '[');
+                        let name = items.next().unwrap();
+                        match items.next() {
+                            Some(index) => format!("[{:?}][{}", name, index),
+                            None => format!("[{:?}]", name),
+                        }
+                    })
+                    .collect();
+                format!("pm.response.json(){}", path)
+            }
+        };
+        exec.push(format!(
+            "pm.environment.set({:?}, {});",
+            opt.option_name(),
+            value
+        ));
+    }
+    exec
+}
+
+fn postman_variables(b: &Bastion, env: &str) -> Result<Vec<Value>> {
+    // only export variables defined by the user, without secrets
+    Ok(Variable::get_by(b.conn(), |x| {
+        x.environment() == env && x.source() == Some("user") && !x.is_secret()
+    })?
+    .iter()
+    .map(|x| {
+        json!({
+            "key": x.name(),
+            "value": x.value().map(to_postman_placeholders).unwrap_or_default(),
+        })
+    })
+    .collect())
+}
+
+fn to_postman_placeholders(s: &str) -> String {
+    // repost uses {name} where postman uses {{name}}
+    let re = Regex::new(InputOption::PATTERN).unwrap();
+    String::from(re.replace_all(s, "{{$2}}"))
+}
+
+pub fn curl_command(method: &str, url: &str, headers: Vec<&str>, body: Option<&[u8]>) -> String {
+    let mut args = vec![String::from("curl")];
+    // only add the method when curl would not infer it
+    match (method, body.is_some()) {
+        ("GET", false) | ("POST", true) => (),
+        ("HEAD", _) => args.push(String::from("--head")),
+        (method, _) => {
+            args.push(String::from("-X"));
+            args.push(String::from(method));
+        }
+    }
+    args.push(shell_quote(url));
+    for header in headers {
+        let header = header.trim();
+        if header.is_empty() {
+            continue;
+        }
+        args.push(String::from("-H"));
+        args.push(shell_quote(header));
+    }
+    if let Some(body) = body {
+        args.push(String::from("--data-binary"));
+        args.push(shell_quote(&String::from_utf8_lossy(body)));
+    }
+    args.join(" ")
+}
+
+fn shell_quote(s: &str) -> String {
+    let safe = |c: char| c.is_ascii_alphanumeric() || "-_./:=@%+,".contains(c);
+    if !s.is_empty() && s.chars().all(safe) {
+        return String::from(s);
+    }
+    format!("'{}'", s.replace('\'', r"'\''"))
+}
@@ -0,0 +1,990 @@
+use crate::bastion::Bastion;
+use crate::db::{Auth, DbObject, InputOption, OutputOption, Request, Variable, WorkspaceFile};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use colored::*;
+use percent_encoding::{percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use regex::Regex;
+use reqwest::Method;
+use serde_yaml::Value;
+use std::fs;
+
+pub fn curl(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because command is required
+    let command = matches.value_of("command").unwrap();
+    let args = shlex::split(command).ok_or(Error::new(ErrorKind::ParseError))?;
+    let request = parse_curl(matches.value_of("name"), args)?;
+
+    request.create(b.conn())?;
+    b.set_options(InputOption::get_by_name(b.conn(), request.name())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+pub fn workspace(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because file is required
+    let file: WorkspaceFile =
+        serde_yaml::from_str(&fs::read_to_string(matches.value_of("file").unwrap())?)?;
+    let allow_sources = matches.is_present("allow-sources");
+    if allow_sources {
+        for (name, environment, value) in file.sourced_variables() {
+            println!(
+                "{}",
+                format!("[!] {} ({}) will be read from {}", name, environment, value)
+                    .red()
+                    .bold()
+            );
+        }
+    }
+    file.to_db(b.conn(), matches.is_present("replace"), allow_sources)?;
+
+    b.set_state()?;
+    b.set_options(InputOption::get_all(b.conn())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+pub fn openapi(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because file is required
+    let spec: Value =
+        serde_yaml::from_str(&fs::read_to_string(matches.value_of("file").unwrap())?)?;
+    let host_var = matches.value_of("variable").unwrap_or("host");
+    let servers = openapi_servers(&spec, matches.value_of("base-url"))?;
+
+    for request in openapi_requests(&spec, host_var)? {
+        if Request::exists(b.conn(), request.name())? {
+            println!("Request '{}' already exists, skipping.", request.name());
+            continue;
+        }
+        request.create(b.conn())?;
+    }
+    for (environment, value) in servers {
+        let mut var = Variable::get_by(b.conn(), |x| {
+            x.name() == host_var && x.environment() == environment
+        })?;
+        let mut var = match var.len() {
+            0 => Variable::new(host_var, &environment, Some(&value), Some("user")),
+            _ => var.remove(0),
+        };
+        set_imported_value(&mut var, &value);
+        var.upsert(b.conn())?;
+    }
+
+    b.set_options(InputOption::get_all(b.conn())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+pub fn postman(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because file is required
+    let collection: serde_json::Value =
+        serde_json::from_str(&fs::read_to_string(matches.value_of("file").unwrap())?)?;
+    let items = match collection.get("item").and_then(|x| x.as_array()) {
+        Some(x) => x,
+        None => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "No items found in Postman collection.",
+            )))
+        }
+    };
+
+    let mut requests = vec![];
+    postman_items(items, "", &mut requests)?;
+    for (request, output_opts) in requests {
+        if Request::exists(b.conn(), request.name())? {
+            println!("Request '{}' already exists, skipping.", request.name());
+            continue;
+        }
+        request.create(b.conn())?;
+        for opt in output_opts {
+            opt.create(b.conn())?;
+        }
+    }
+
+    // collection variables belong to the current environment
+    let environment = b.current_environment().unwrap_or("default").to_owned();
+    let mut variables = postman_variables(collection.get("variable"), &environment);
+    for filename in matches.values_of("environment").unwrap_or_default() {
+        let env: serde_json::Value = serde_json::from_str(&fs::read_to_string(filename)?)?;
+        let name = env
+            .get("name")
+            .and_then(|x| x.as_str())
+            .map(slugify)
+            .unwrap_or(String::from("default"));
+        variables.extend(postman_variables(env.get("values"), &name));
+    }
+    for (name, environment, value) in variables {
+        let mut var = Variable::get_by(b.conn(), |x| {
+            x.name() == name && x.environment() == environment
+        })?;
+        let mut var = match var.len() {
+            0 => Variable::new(&name, &environment, Some(&value), Some("user")),
+            _ => var.remove(0),
+        };
+        set_imported_value(&mut var, &value);
+        var.upsert(b.conn())?;
+    }
+
+    b.set_options(InputOption::get_all(b.conn())?)?;
+    b.set_completions()?;
+    Ok(())
+}
+
+// Imported values are literal, even when they replace a variable that is
+// secret or read from the environment, a file, or a command
+fn set_imported_value(var: &mut Variable, value: &str) {
+    if var.is_user_defined() {
+        var.set_source(Some(Variable::USER));
+    }
+    var.set_secret(false);
+    var.set_value(Some(value));
+}
+
+fn postman_items(
+    items: &Vec<serde_json::Value>,
+    prefix: &str,
+    requests: &mut Vec<(Request, Vec<OutputOption>)>,
+) -> Result<()> {
+    for item in items {
+        let name = request_name(item.get("name").and_then(|x| x.as_str()).unwrap_or(""));
+        let name = match (prefix.len(), name.len()) {
+            (0, _) => name,
+            (_, 0) => String::from(prefix),
+            _ => format!("{}-{}", prefix, name),
+        };
+        // folders contain more items
+        if let Some(items) = item.get("item").and_then(|x| x.as_array()) {
+            postman_items(items, &name, requests)?;
+            continue;
+        }
+        let req = match item.get("request") {
+            Some(x) => x,
+            None => continue,
+        };
+        requests.push(postman_request(&name, req, item.get("event"))?);
+    }
+    Ok(())
+}
+
+fn postman_request(
+    name: &str,
+    req: &serde_json::Value,
+    events: Option<&serde_json::Value>,
+) -> Result<(Request, Vec<OutputOption>)> {
+    // requests may be a plain URL string
+    let url = match req.get("url").unwrap_or(req) {
+        serde_json::Value::String(x) => x.as_str(),
+        x => x.get("raw").and_then(|x| x.as_str()).unwrap_or(""),
+    };
+    let method = req
+        .get("method")
+        .and_then(|x| x.as_str())
+        .map(|x| Method::from_bytes(x.as_bytes()).unwrap_or(Method::GET))
+        .unwrap_or(Method::GET);
+    let mut request = Request::new(name, Some(method), &postman_to_placeholders(url));
+
+    let headers = req.get("header").and_then(|x| x.as_array());
+    for header in headers.into_iter().flatten() {
+        if header.get("disabled").and_then(|x| x.as_bool()) == Some(true) {
+            continue;
+        }
+        let key = header.get("key").and_then(|x| x.as_str());
+        let value = header.get("value").and_then(|x| x.as_str());
+        if let (Some(key), Some(value)) = (key, value) {
+            request.add_header(key, &postman_to_placeholders(value));
+        }
+    }
+
+    let body = req.get("body");
+    let body = match body.and_then(|x| x.get("mode")).and_then(|x| x.as_str()) {
+        Some("raw") => body
+            .and_then(|x| x.get("raw"))
+            .and_then(|x| x.as_str())
+            .map(String::from),
+        Some("urlencoded") => {
+            let params = body
+                .and_then(|x| x.get("urlencoded"))
+                .and_then(|x| x.as_array());
+            let params: Vec<String> = params
+                .into_iter()
+                .flatten()
+                .filter(|x| x.get("disabled").and_then(|x| x.as_bool()) != Some(true))
+                .map(|x| {
+                    format!(
+                        "{}={}",
+                        x.get("key").and_then(|x| x.as_str()).unwrap_or(""),
+                        x.get("value").and_then(|x| x.as_str()).unwrap_or("")
+                    )
+                })
+                .collect();
+            Some(params.join("&"))
+        }
+        Some("graphql") => body.and_then(|x| x.get("graphql")).map(|x| x.to_string()),
+        Some(x) => {
+            println!("Unsupported body mode '{}' for request '{}'.", x, name);
+            None
+        }
+        None => None,
+    };
+    if let Some(body) = body {
+        request.set_body(Some(postman_to_placeholders(&body).into_bytes()));
+    }
+
+    // extractions exported by repost are stored as comments in test scripts
+    let re = Regex::new(r"^// repost: extract (body|header) (\S+) --to-var (\S+)$")?;
+    let mut output_opts = vec![];
+    for event in events.and_then(|x| x.as_array()).into_iter().flatten() {
+        let exec = event
+            .get("script")
+            .and_then(|x| x.get("exec"))
+            .and_then(|x| x.as_array());
+        for line in exec.into_iter().flatten().filter_map(|x| x.as_str()) {
+            if let Some(cap) = re.captures(line.trim()) {
+                output_opts.push(OutputOption::new(name, &cap[3], &cap[1], &cap[2]));
+            }
+        }
+    }
+    Ok((request, output_opts))
+}
+
+fn postman_variables(
+    variables: Option<&serde_json::Value>,
+    environment: &str,
+) -> Vec<(String, String, String)> {
+    let variables = variables.and_then(|x| x.as_array());
+    variables
+        .into_iter()
+        .flatten()
+        .filter(|x| x.get("enabled").and_then(|x| x.as_bool()) != Some(false))
+        .filter(|x| x.get("disabled").and_then(|x| x.as_bool()) != Some(true))
+        .filter_map(|x| {
+            let key = x.get("key").and_then(|x| x.as_str())?;
+            let value = match x.get("value")? {
+                serde_json::Value::String(x) => x.clone(),
+                x => x.to_string(),
+            };
+            Some((
+                String::from(key),
+                String::from(environment),
+                postman_to_placeholders(&value),
+            ))
+        })
+        .collect()
+}
+
+fn postman_to_placeholders(s: &str) -> String {
+    // postman uses {{name}} where repost uses {name}
+    let re = Regex::new(r"\{\{\s*([\w.-]+)\s*\}\}").unwrap();
+    String::from(re.replace_all(s, "{$1}"))
+}
+
+fn openapi_requests(spec: &Value, host_var: &str) -> Result<Vec<Request>> {
+    let paths = match spec.get("paths").and_then(Value::as_mapping) {
+        Some(x) => x,
+        None => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "No paths found in OpenAPI spec.",
+            )))
+        }
+    };
+    let methods = [
+        "get", "put", "post", "delete", "options", "head", "patch", "trace",
+    ];
+
+    let mut requests = vec![];
+    for (path, item) in paths.iter() {
+        let path = match path.as_str() {
+            Some(x) => x,
+            None => continue,
+        };
+        for method_name in methods.iter() {
+            let op = match item.get(method_name) {
+                Some(x) => x,
+                None => continue,
+            };
+            let method = Method::from_bytes(method_name.to_uppercase().as_bytes()).unwrap();
+            let name = match op.get("operationId").and_then(Value::as_str) {
+                Some(x) => String::from(x),
+                None => openapi_name(method_name, path),
+            };
+
+            // parameters can be defined for the path or the operation
+            let params: Vec<&Value> = item
+                .get("parameters")
+                .and_then(Value::as_sequence)
+                .into_iter()
+                .chain(op.get("parameters").and_then(Value::as_sequence))
+                .flatten()
+                .map(|x| openapi_resolve(spec, x))
+                .collect::<Result<_>>()?;
+            let mut url = format!("{{{}}}{}", host_var, path);
+            let mut query = vec![];
+            let mut body = None;
+            for param in params {
+                let param_name = param.get("name").and_then(Value::as_str);
+                match (param.get("in").and_then(Value::as_str), param_name) {
+                    (Some("query"), Some(param_name)) => {
+                        query.push(format!("{}={{{}}}", param_name, param_name))
+                    }
+                    // swagger 2.0 body parameter
+                    (Some("body"), _) => {
+                        body = match param.get("schema") {
+                            Some(x) => openapi_resolve(spec, x)?.get("example").cloned(),
+                            None => None,
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            if !query.is_empty() {
+                url = format!("{}?{}", url, query.join("&"));
+            }
+
+            let mut request = Request::new(&name, Some(method), &url);
+            let request_body = match op.get("requestBody") {
+                Some(x) => Some(openapi_resolve(spec, x)?),
+                None => None,
+            };
+            let content = request_body
+                .and_then(|x| x.get("content"))
+                .and_then(Value::as_mapping)
+                .and_then(|x| x.iter().next());
+            if let Some((content_type, media)) = content {
+                body = openapi_example(spec, media)?;
+                if let Some(content_type) = content_type.as_str() {
+                    request.add_header("Content-Type", content_type);
+                }
+            }
+            if let Some(body) = body {
+                let body = match body {
+                    Value::String(x) => x,
+                    x => serde_json::to_string_pretty(&x)?,
+                };
+                request.set_body(Some(body.into_bytes()));
+            }
+            requests.push(request);
+        }
+    }
+    Ok(requests)
+}
+
+fn openapi_example(spec: &Value, media: &Value) -> Result<Option<Value>> {
+    if let Some(example) = media.get("example") {
+        return Ok(Some(example.clone()));
+    }
+    if let Some(examples) = media.get("examples").and_then(Value::as_mapping) {
+        if let Some((_, example)) = examples.iter().next() {
+            return Ok(openapi_resolve(spec, example)?.get("value").cloned());
+        }
+    }
+    Ok(match media.get("schema") {
+        Some(x) => openapi_resolve(spec, x)?.get("example").cloned(),
+        None => None,
+    })
+}
+
+// Follow local references such as {$ref: '#/components/parameters/id'}.
+// Values without a reference, or with a local one that cannot be found, are
+// returned as they are. References to other files are not supported.
+fn openapi_resolve<'a>(spec: &'a Value, mut value: &'a Value) -> Result<&'a Value> {
+    // references may point to other references, but not forever
+    for _ in 0..16 {
+        let pointer =
+            match value.get("$ref").and_then(Value::as_str) {
+                Some(x) if x.starts_with("#/") => &x[2..],
+                Some(_) => return Err(Error::new(ErrorKind::ArgumentError(
+                    "Found a $ref to another file. Only references within the spec are supported.",
+                ))),
+                None => return Ok(value),
+            };
+        let mut target = Some(spec);
+        for token in pointer.split('/') {
+            let token = token.replace("~1", "/").replace("~0", "~");
+            target = target.and_then(|x| x.get(token.as_str()));
+        }
+        value = match target {
+            Some(x) => x,
+            None => return Ok(value),
+        };
+    }
+    Ok(value)
+}
+
+fn openapi_servers(spec: &Value, base_url: Option<&str>) -> Result<Vec<(String, String)>> {
+    // swagger 2.0 uses host, basePath, and schemes instead of servers
+    if let Some(host) = spec.get("host").and_then(Value::as_str) {
+        let base_path = spec.get("basePath").and_then(Value::as_str).unwrap_or("");
+        let scheme = spec
+            .get("schemes")
+            .and_then(|x| x.get(0))
+            .and_then(Value::as_str)
+            .unwrap_or("https");
+        return Ok(vec![(
+            String::from("default"),
+            format!("{}://{}{}", scheme, host, base_path.trim_end_matches('/')),
+        )]);
+    }
+
+    let servers = match spec.get("servers").and_then(Value::as_sequence) {
+        Some(x) => x,
+        None => return Ok(vec![]),
+    };
+    let mut result = vec![];
+    for (i, server) in servers.iter().enumerate() {
+        let mut url = match server.get("url").and_then(Value::as_str) {
+            Some(x) => String::from(x.trim_end_matches('/')),
+            None => continue,
+        };
+        // server URLs may be relative to where the spec is hosted
+        if !url.contains("://") {
+            url = match base_url {
+                Some(base) => {
+                    let base = base.trim_end_matches('/');
+                    format!("{}/{}", base, url.trim_start_matches('/'))
+                        .trim_end_matches('/')
+                        .to_string()
+                }
+                None => {
+                    return Err(Error::new(ErrorKind::ArgumentError(
+                        "Found a relative server URL. Use --base-url to resolve it.",
+                    )))
+                }
+            };
+        }
+        // substitute server variables with their default value
+        if let Some(vars) = server.get("variables").and_then(Value::as_mapping) {
+            for (name, var) in vars.iter() {
+                let default = var.get("default").and_then(Value::as_str);
+                if let (Some(name), Some(default)) = (name.as_str(), default) {
+                    url = url.replace(&format!("{{{}}}", name), default);
+                }
+            }
+        }
+        let environment = match server.get("description").and_then(Value::as_str) {
+            Some(x) => slugify(x),
+            None => format!("server{}", i + 1),
+        };
+        result.push((environment, url));
+    }
+    Ok(result)
+}
+
+fn openapi_name(method: &str, path: &str) -> String {
+    format!("{}-{}", method, slugify(path))
+}
+
+fn request_name(s: &str) -> String {
+    s.split(|c: char| !c.is_alphanumeric() && c != '-' && c != '_')
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn slugify(s: &str) -> String {
+    s.to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|x| !x.is_empty())
+        .collect::<Vec<_>>()
+        .join("-")
+}
+
+fn parse_curl(name: Option<&str>, args: Vec<String>) -> Result<Request> {
+    let mut args = args.into_iter().peekable();
+    if args.peek().map(|x| x == "curl") == Some(true) {
+        args.next();
+    }
+
+    let mut url: Option<String> = None;
+    let mut method: Option<Method> = None;
+    let mut headers: Vec<(String, String)> = vec![];
+    let mut data: Vec<Vec<u8>> = vec![];
+    let mut auth: Option<Auth> = None;
+    let mut get = false;
+
+    while let Some(arg) = args.next() {
+        if !arg.starts_with('-') || arg == "-" {
+            url = Some(arg);
+            continue;
+        }
+        for (flag, attached) in split_curl_flags(&arg) {
+            let flag = flag.as_ref();
+
+            // flags that do not take a value
+            match flag {
+                "-G" | "--get" => {
+                    get = true;
+                    continue;
+                }
+                "-I" | "--head" => {
+                    method = Some(Method::HEAD);
+                    continue;
+                }
+                _ if !curl_takes_value(flag) => continue,
+                _ => (),
+            }
+
+            let value = match attached.or_else(|| args.next()) {
+                Some(x) => x,
+                None => {
+                    return Err(Error::new(ErrorKind::ArgumentError(
+                        "Missing value for curl option.",
+                    )))
+                }
+            };
+            match flag {
+                "-X" | "--request" => {
+                    method = Some(
+                        Method::from_bytes(value.as_bytes())
+                            .map_err(|_| Error::new(ErrorKind::ParseError))?,
+                    );
+                }
+                "-H" | "--header" => {
+                    let mut items = value.splitn(2, ':');
+                    let (header, value) = (items.next(), items.next());
+                    if header.and(value).is_none() {
+                        return Err(Error::new(ErrorKind::ArgumentError(
+                            "Found header that does not contain ':'",
+                        )));
+                    }
+                    headers.push((
+                        String::from(header.unwrap().trim()),
+                        String::from(value.unwrap().trim()),
+                    ));
+                }
+                "-d" | "--data" | "--data-ascii" | "--data-binary" => {
+                    if value.starts_with('@') {
+                        let mut filename = value.chars();
+                        filename.next(); // discard @
+                        data.push(fs::read(filename.collect::<String>())?);
+                    } else {
+                        data.push(value.into_bytes());
+                    }
+                }
+                "--data-raw" => data.push(value.into_bytes()),
+                "--data-urlencode" => data.push(curl_urlencode(&value)?),
+                "-u" | "--user" => {
+                    // curl asks for the password when it is left out
+                    let (user, password) = value.split_once(':').unwrap_or((&value, ""));
+                    auth = Some(Auth::Basic(String::from(user), String::from(password)));
+                }
+                "-A" | "--user-agent" => headers.push((String::from("User-Agent"), value)),
+                "-e" | "--referer" => headers.push((String::from("Referer"), value)),
+                "-b" | "--cookie" => headers.push((String::from("Cookie"), value)),
+                "--url" => url = Some(value),
+                // ignore other options that take a value
+                _ => (),
+            }
+        }
+    }
+
+    let url = match url {
+        // curl defaults to http when no scheme is given
+        Some(x) if !x.contains("://") => format!("http://{}", x),
+        Some(x) => x,
+        None => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "No URL found in curl command.",
+            )))
+        }
+    };
+    // curl sends data with POST unless -G is used
+    let method = match (method, data.len(), get) {
+        (Some(method), _, _) => method,
+        (None, 0, _) | (None, _, true) => Method::GET,
+        (None, _, false) => Method::POST,
+    };
+    let name = match name {
+        Some(name) => String::from(name),
+        None => Request::name_from_url(&method, &url),
+    };
+
+    let mut request = Request::new(&name, Some(method), &url);
+    for header in headers {
+        request.add_header(&header.0, &header.1);
+    }
+    request.set_auth(auth);
+    if !data.is_empty() {
+        let data = data.join(&b'&');
+        if get {
+            request.add_query_param(&String::from_utf8_lossy(&data));
+        } else {
+            request.set_body(Some(data));
+        }
+    }
+    Ok(request)
+}
+
+// Split an argument into curl options and their attached values. Short
+// options may be clustered (-sX POST), and the first one that takes a value
+// takes the rest of the argument as its value (-XPOST).
+fn split_curl_flags(arg: &str) -> Vec<(String, Option<String>)> {
+    if arg.starts_with("--") {
+        return vec![match arg.split_once('=') {
+            Some((flag, value)) => (String::from(flag), Some(String::from(value))),
+            None => (String::from(arg), None),
+        }];
+    }
+    let mut flags = vec![];
+    for (i, c) in arg.char_indices().skip(1) {
+        let flag = format!("-{}", c);
+        if curl_takes_value(&flag) {
+            let rest = &arg[i + c.len_utf8()..];
+            flags.push((flag, Some(String::from(rest)).filter(|x| !x.is_empty())));
+            break;
+        }
+        flags.push((flag, None));
+    }
+    flags
+}
+
+// The characters curl leaves unencoded
+const URLENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_')
+    .remove(b'~');
+
+// Encode a --data-urlencode value the way curl does: content, =content,
+// name=content, @file or name@file, where only the content is encoded
+fn curl_urlencode(value: &str) -> Result<Vec<u8>> {
+    let encode = |x: &[u8]| percent_encode(x, URLENCODE_SET).to_string();
+    let (name, content) = match value.find('=').or_else(|| value.find('@')) {
+        Some(i) if value[i..].starts_with('@') => {
+            (&value[..i], encode(&fs::read(&value[i + 1..])?))
+        }
+        Some(i) => (&value[..i], encode(&value.as_bytes()[i + 1..])),
+        None => ("", encode(value.as_bytes())),
+    };
+    Ok(match name.is_empty() {
+        true => content.into_bytes(),
+        false => format!("{}={}", name, content).into_bytes(),
+    })
+}
+
+fn curl_takes_value(flag: &str) -> bool {
+    matches!(
+        flag,
+        "-X" | "--request"
+            | "-H"
+            | "--header"
+            | "-d"
+            | "--data"
+            | "--data-ascii"
+            | "--data-binary"
+            | "--data-raw"
+            | "--data-urlencode"
+            | "-u"
+            | "--user"
+            | "-A"
+            | "--user-agent"
+            | "-e"
+            | "--referer"
+            | "-b"
+            | "--cookie"
+            | "--url"
+            | "-o"
+            | "--output"
+            | "-c"
+            | "--cookie-jar"
+            | "-m"
+            | "--max-time"
+            | "--connect-timeout"
+            | "-x"
+            | "--proxy"
+            | "-E"
+            | "--cert"
+            | "--key"
+            | "--cacert"
+            | "-F"
+            | "--form"
+            | "-w"
+            | "--write-out"
+            | "-T"
+            | "--upload-file"
+            | "-r"
+            | "--range"
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn curl(command: &str) -> Request {
+        parse_curl(None, shlex::split(command).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn curl_method_and_url() {
+        let request = curl("curl localhost:8000/users/1");
+        assert_eq!(request.name(), "get-1");
+        assert_eq!(request.method(), &Method::GET);
+        assert_eq!(request.url(), "http://localhost:8000/users/1");
+
+        let request = curl("curl -X DELETE https://localhost/users/1");
+        assert_eq!(request.method(), &Method::DELETE);
+        let request = curl("curl -sXPUT https://localhost/users/1");
+        assert_eq!(request.method(), &Method::PUT);
+        let request = curl("curl --request=PATCH --url https://localhost/users");
+        assert_eq!(request.method(), &Method::PATCH);
+        assert_eq!(request.url(), "https://localhost/users");
+    }
+
+    #[test]
+    fn curl_headers() {
+        let request =
+            curl(r#"curl -H 'Accept: application/json' -H"X-Id:  {id} " -A repost localhost"#);
+        assert_eq!(
+            request.headers().as_deref(),
+            Some("Accept: application/json\nX-Id: {id}\nUser-Agent: repost")
+        );
+        assert!(parse_curl(None, vec!["-H".into(), "Accept".into(), "x".into()]).is_err());
+    }
+
+    #[test]
+    fn curl_data() {
+        let request = curl(r#"curl -d 'a=1' --data "b=two words" localhost/x"#);
+        assert_eq!(request.method(), &Method::POST);
+        assert_eq!(request.body().as_deref(), Some(&b"a=1&b=two words"[..]));
+
+        let request = curl("curl -G -d a=1 -d b=2 localhost/x?c=3");
+        assert_eq!(request.method(), &Method::GET);
+        assert_eq!(request.url(), "http://localhost/x?c=3&a=1&b=2");
+        assert_eq!(request.body(), &None);
+    }
+
+    #[test]
+    fn curl_data_urlencode() {
+        let request = curl("curl --data-urlencode 'q=a b&c' --data-urlencode =é localhost");
+        assert_eq!(request.body().as_deref(), Some(&b"q=a%20b%26c&%C3%A9"[..]));
+    }
+
+    #[test]
+    fn curl_urlencode_forms() {
+        assert_eq!(curl_urlencode("a b").unwrap(), b"a%20b");
+        assert_eq!(curl_urlencode("=a=b").unwrap(), b"a%3Db");
+        assert_eq!(curl_urlencode("name=a-b.c_d~").unwrap(), b"name=a-b.c_d~");
+        assert!(curl_urlencode("name@/nonexistent/file").is_err());
+    }
+
+    #[test]
+    fn curl_user() {
+        let request = curl("curl -u user:pass localhost");
+        assert_eq!(
+            request.auth(),
+            Some(&Auth::Basic(String::from("user"), String::from("pass")))
+        );
+        assert_eq!(request.headers(), &None);
+        let request = curl("curl --user=user localhost");
+        assert_eq!(
+            request.auth(),
+            Some(&Auth::Basic(String::from("user"), String::new()))
+        );
+    }
+
+    #[test]
+    fn curl_missing_values() {
+        assert!(parse_curl(None, vec!["curl".into(), "-X".into()]).is_err());
+        assert!(parse_curl(None, vec!["curl".into(), "-s".into()]).is_err());
+    }
+    const OPENAPI_SPEC: &str = r#"
+openapi: 3.0.0
+servers:
+  - url: https://{region}.example.com/v1/
+    description: Production API
+    variables:
+      region:
+        default: eu
+  - url: /v2
+paths:
+  /users:
+    get:
+      parameters:
+        - name: limit
+          in: query
+        - $ref: '#/components/parameters/Sort'
+    post:
+      operationId: create-user
+      requestBody:
+        $ref: '#/components/requestBodies/User'
+  /users/{id}:
+    parameters:
+      - name: id
+        in: path
+    delete: {}
+components:
+  parameters:
+    Sort:
+      name: sort
+      in: query
+  requestBodies:
+    User:
+      content:
+        application/json:
+          schema:
+            $ref: '#/components/schemas/User'
+  schemas:
+    User:
+      example:
+        name: alice
+"#;
+
+    #[test]
+    fn openapi_paths_and_parameters() {
+        let spec: Value = serde_yaml::from_str(OPENAPI_SPEC).unwrap();
+        let requests = openapi_requests(&spec, "host").unwrap();
+        let requests: Vec<_> = requests
+            .iter()
+            .map(|x| (x.name(), x.method().as_str(), x.url()))
+            .collect();
+        assert_eq!(
+            requests,
+            vec![
+                ("get-users", "GET", "{host}/users?limit={limit}&sort={sort}"),
+                ("create-user", "POST", "{host}/users"),
+                ("delete-users-id", "DELETE", "{host}/users/{id}"),
+            ]
+        );
+    }
+
+    #[test]
+    fn openapi_request_body() {
+        let spec: Value = serde_yaml::from_str(OPENAPI_SPEC).unwrap();
+        let request = openapi_requests(&spec, "host").unwrap().remove(1);
+        assert_eq!(
+            request.headers().as_deref(),
+            Some("Content-Type: application/json")
+        );
+        assert_eq!(
+            request.body().as_deref(),
+            Some(&b"{\n  \"name\": \"alice\"\n}"[..])
+        );
+    }
+
+    #[test]
+    fn openapi_servers_as_environments() {
+        let spec: Value = serde_yaml::from_str(OPENAPI_SPEC).unwrap();
+        assert!(openapi_servers(&spec, None).is_err());
+        assert_eq!(
+            openapi_servers(&spec, Some("http://localhost:8000/")).unwrap(),
+            vec![
+                (
+                    String::from("production-api"),
+                    String::from("https://eu.example.com/v1")
+                ),
+                (
+                    String::from("server2"),
+                    String::from("http://localhost:8000/v2")
+                ),
+            ]
+        );
+
+        let spec: Value =
+            serde_yaml::from_str("host: api.example.com\nbasePath: /v1/\nschemes: [http]").unwrap();
+        assert_eq!(
+            openapi_servers(&spec, None).unwrap(),
+            vec![(
+                String::from("default"),
+                String::from("http://api.example.com/v1")
+            )]
+        );
+    }
+
+    #[test]
+    fn openapi_remote_ref() {
+        let spec: Value = serde_yaml::from_str(
+            "paths:\n  /users:\n    get:\n      parameters:\n        - $ref: 'common.yaml#/Sort'",
+        )
+        .unwrap();
+        let err = openapi_requests(&spec, "host").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::new(ErrorKind::ArgumentError(
+                "Found a $ref to another file. Only references within the spec are supported."
+            ))
+            .to_string()
+        );
+    }
+    // The requests, variables, and output options of a workspace, in a form
+    // that can be compared
+    fn workspace_contents(b: &Bastion) -> Vec<String> {
+        let requests = Request::get_all(b.conn()).unwrap().into_iter().map(|x| {
+            format!(
+                "{} {} {} {:?} {:?}",
+                x.name(),
+                x.method(),
+                x.url(),
+                x.headers(),
+                x.body()
+                    .as_ref()
+                    .map(|x| String::from_utf8_lossy(x).into_owned())
+            )
+        });
+        let variables = Variable::get_all(b.conn())
+            .unwrap()
+            .into_iter()
+            .map(|x| format!("{} {} {:?}", x.name(), x.environment(), x.value()));
+        let output_opts = OutputOption::get_all(b.conn())
+            .unwrap()
+            .into_iter()
+            .map(|x| {
+                format!(
+                    "{} {} {} {}",
+                    x.request_name(),
+                    x.option_name(),
+                    x.extraction_type(),
+                    x.extraction_source()
+                )
+            });
+        let mut contents: Vec<String> = requests.chain(variables).chain(output_opts).collect();
+        contents.sort();
+        contents
+    }
+
+    #[test]
+    fn postman_round_trip() {
+        let dir = TempDir::new().unwrap();
+        let file = dir.path().join("collection.json");
+        let file = file.to_str().unwrap();
+
+        let exported = TempDir::new().unwrap();
+        let mut b = Bastion::new(exported.path().to_path_buf(), false).unwrap();
+        for command in &[
+            "create variable host local=localhost:8000 prod=example.com",
+            "create variable user-id local=1",
+            "create request get-user http://{host}/users/{user-id} -H 'Accept: application/json'",
+            r#"create request create-user http://{host}/users -d '{"name": "{name}"}'"#,
+            "set request create-user",
+            "extract body id --to-var user-id",
+            "extract header Location --to-var location",
+        ] {
+            b.execute(command).unwrap();
+        }
+        // without a current environment, all variables are written to the
+        // environment files
+        b.execute(&format!("export postman {} --environments", file))
+            .unwrap();
+
+        let imported = TempDir::new().unwrap();
+        let mut imported = Bastion::new(imported.path().to_path_buf(), false).unwrap();
+        let environments: Vec<String> = ["local", "prod"]
+            .iter()
+            .map(|env| {
+                let path = dir
+                    .path()
+                    .join(format!("collection.{}.postman_environment.json", env));
+                format!("-e {}", path.display())
+            })
+            .collect();
+        imported
+            .execute(&format!(
+                "import postman {} {}",
+                file,
+                environments.join(" ")
+            ))
+            .unwrap();
+
+        assert_eq!(workspace_contents(&imported), workspace_contents(&b));
+    }
+}
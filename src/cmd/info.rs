@@ -1,11 +1,12 @@
 use crate::bastion::Bastion;
-use crate::db::{DbObject, OutputOption, Request};
+use crate::db::auth::MASK;
+use crate::db::{Assertion, DbObject, OutputOption, Request};
 use crate::error::{Error, ErrorKind, Result};
 use clap_v3::ArgMatches;
 use comfy_table::{Cell, CellAlignment, ContentArrangement, Table};
 use terminal_size::{terminal_size, Width};
 
-pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
+pub fn execute(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     if b.current_request().is_none() {
         return Err(Error::new(ErrorKind::ArgumentError(
             "Info is only available in a request specific context. Try setting a request first.",
@@ -13,10 +14,14 @@ pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
     }
     let req = b.current_request().unwrap();
     // display request, input options, and output options
-    let req = Request::get_by_name(b.conn(), req)?.remove(0);
+    let mut req = Request::get_by_name(b.conn(), req)?.remove(0);
+    if let Some(combination) = matches.value_of("combine") {
+        req.set_combination(Some(combination));
+    }
     // get options for this request
     let input_opts = req.input_options();
     let output_opts = OutputOption::get_by_name(b.conn(), req.name())?;
+    let assertions = Assertion::get_by_name(b.conn(), req.name())?;
 
     let mut width = 76;
     if let Some((Width(w), _)) = terminal_size() {
@@ -57,6 +62,16 @@ pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
         Cell::new("Body?:").set_alignment(CellAlignment::Right),
         Cell::new(has_body),
     ]);
+    table.add_row(vec![
+        Cell::new("Combination:").set_alignment(CellAlignment::Right),
+        Cell::new(req.combination()),
+    ]);
+    if let Some(auth) = req.auth() {
+        table.add_row(vec![
+            Cell::new("Auth:").set_alignment(CellAlignment::Right),
+            Cell::new(auth.to_auth_string()),
+        ]);
+    }
     println!();
     for line in table.to_string().split('\n') {
         println!("  {}", line);
@@ -71,9 +86,18 @@ pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
             .set_content_arrangement(ContentArrangement::Dynamic)
             .set_table_width(width);
         println!("  Input Options");
-        table.set_header(vec!["name", "current values"]);
+        table.set_header(vec!["name", "current values", "default"]);
+        let secrets = req.secret_option_names();
         for opt in input_opts {
-            table.add_row(vec![opt.option_name(), &opt.values().join("\n")]);
+            let values = match secrets.contains(opt.option_name()) {
+                true => vec![MASK; opt.values().len()],
+                false => opt.values(),
+            };
+            table.add_row(vec![
+                opt.option_name(),
+                &values.join("\n"),
+                &opt.default_summary(),
+            ]);
         }
         for line in table.to_string().split('\n') {
             println!("  {}", line);
@@ -103,9 +127,34 @@ pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
         println!();
     }
 
+    // print assertions
+    if !assertions.is_empty() {
+        let mut table = Table::new();
+        table
+            .load_preset(super::show::TABLE_FORMAT)
+            .set_content_arrangement(ContentArrangement::Dynamic)
+            .set_table_width(width);
+        println!("  Assertions");
+        table.set_header(vec!["id", "type", "source", "comparison", "expected"]);
+        for a in assertions {
+            table.add_row(vec![
+                &a.id().to_string(),
+                a.assertion_type(),
+                a.source(),
+                a.comparison(),
+                a.expected().unwrap_or(""),
+            ]);
+        }
+        for line in table.to_string().split('\n') {
+            println!("  {}", line);
+        }
+        println!();
+    }
+
     // print planned requests
-    let requests = super::run::create_requests(&req).unwrap_or(vec![]);
-    println!("  Planned Requests");
+    req.mask_input_options();
+    let requests = super::run::planned_requests(&req).unwrap_or_default();
+    println!("  Planned Requests ({})", req.combination());
     super::show::print_table(requests);
     println!();
 
@@ -1,8 +1,17 @@
+pub mod assert;
+pub mod auth;
+pub mod bench;
 pub mod cmd;
 pub mod create;
 pub mod delete;
+pub mod diff;
+pub mod edit;
+pub mod export;
 pub mod extract;
+pub mod import;
 pub mod info;
+pub mod oauth2;
 pub mod run;
 pub mod set;
 pub mod show;
+pub mod test;
@@ -0,0 +1,382 @@
+use crate::bastion::Bastion;
+use crate::db::{DbObject, InputOption, OAuth2, Request, Variable};
+use crate::error::{Error, ErrorKind, Result};
+use chrono::{Duration, Utc};
+use colored::*;
+use serde_json::Value;
+
+// Tokens that expire within this many seconds are renewed before use
+const EXPIRY_MARGIN: i64 = 30;
+
+// The access token for an environment. The cached token is used until it
+// expires, then renewed with the cached refresh token if there is one, or
+// else with the configured grant. Set renew to ignore the cached token.
+pub fn access_token(b: &mut Bastion, env: &str, renew: bool) -> Result<String> {
+    let mut config = OAuth2::get_by_name(b.conn(), env)?;
+    if config.is_empty() {
+        return Err(Error::new(ErrorKind::ArgumentError(
+            "The environment has no OAuth2 configuration. Try create oauth2 first.",
+        )));
+    }
+    let config = config.remove(0);
+
+    if !renew {
+        if let Some(token) = cached(b, env, OAuth2::ACCESS_TOKEN_VARIABLE)? {
+            return Ok(token);
+        }
+    }
+    if config.grant_type() != OAuth2::REFRESH_TOKEN {
+        if let Some(refresh_token) = cached(b, env, OAuth2::REFRESH_TOKEN_VARIABLE)? {
+            let mut params = client_params(b, &config)?;
+            params.push(("grant_type", String::from(OAuth2::REFRESH_TOKEN)));
+            params.push(("refresh_token", refresh_token));
+            match request_token(b, &config, params) {
+                Ok(token) => return Ok(token),
+                // fall back to the configured grant
+                Err(x) => println!("{}", format!("[!] {}", x).bright_black()),
+            }
+        }
+    }
+
+    let mut params = client_params(b, &config)?;
+    params.push(("grant_type", String::from(config.grant_type())));
+    match config.grant_type() {
+        OAuth2::PASSWORD => {
+            params.push(("username", variable(b, env, config.username())?));
+            params.push(("password", variable(b, env, config.password())?));
+        }
+        OAuth2::REFRESH_TOKEN => {
+            let refresh_token = match cached(b, env, OAuth2::REFRESH_TOKEN_VARIABLE)? {
+                Some(x) => x,
+                None => variable(b, env, config.refresh_token())?,
+            };
+            params.push(("refresh_token", refresh_token));
+        }
+        _ => (),
+    }
+    request_token(b, &config, params)
+}
+
+// The client credentials and scopes sent with every grant
+fn client_params(b: &mut Bastion, config: &OAuth2) -> Result<Vec<(&'static str, String)>> {
+    let env = config.environment();
+    let mut params = vec![("client_id", variable(b, env, Some(config.client_id()))?)];
+    if config.client_secret().is_some() {
+        params.push(("client_secret", variable(b, env, config.client_secret())?));
+    }
+    if let Some(scopes) = config.scopes() {
+        params.push(("scope", String::from(scopes)));
+    }
+    Ok(params)
+}
+
+// Send the grant to the token endpoint and cache the tokens it returns
+fn request_token(b: &mut Bastion, config: &OAuth2, params: Vec<(&str, String)>) -> Result<String> {
+    let env = config.environment();
+    let client = super::run::create_client(b, Some(env), None)?;
+    let resp = client.post(config.token_url()).form(&params).send()?;
+    let status = resp.status();
+    let body = resp.text()?;
+    if !status.is_success() {
+        let description = serde_json::from_str::<Value>(&body)
+            .ok()
+            .and_then(|x| x["error"].as_str().map(String::from));
+        return Err(Error::new(ErrorKind::TokenRequestFailed(
+            match description {
+                Some(x) => format!("{} ({})", status, x),
+                None => format!("{}", status),
+            },
+        )));
+    }
+    let body: Value = serde_json::from_str(&body).map_err(|_| {
+        Error::new(ErrorKind::TokenRequestFailed(String::from(
+            "the response is not JSON",
+        )))
+    })?;
+    let token = match body["access_token"].as_str() {
+        Some(x) => String::from(x),
+        None => {
+            return Err(Error::new(ErrorKind::TokenRequestFailed(String::from(
+                "the response has no access_token",
+            ))))
+        }
+    };
+
+    let mut var = Variable::new(
+        OAuth2::ACCESS_TOKEN_VARIABLE,
+        env,
+        Some(&token),
+        Some(OAuth2::SOURCE),
+    );
+    var.set_expires(
+        body["expires_in"]
+            .as_i64()
+            .map(|x| Utc::now() + Duration::seconds(x - EXPIRY_MARGIN)),
+    );
+    var.upsert(b.conn())?;
+    if let Some(refresh_token) = body["refresh_token"].as_str() {
+        Variable::new(
+            OAuth2::REFRESH_TOKEN_VARIABLE,
+            env,
+            Some(refresh_token),
+            Some(OAuth2::SOURCE),
+        )
+        .upsert(b.conn())?;
+    }
+
+    // update the requests that use the token
+    b.set_options(InputOption::get_by(b.conn(), |x| {
+        x.option_name() == OAuth2::ACCESS_TOKEN_VARIABLE
+    })?)?;
+    Ok(token)
+}
+
+// A token cached by a previous token request, if it has not expired
+fn cached(b: &Bastion, env: &str, name: &str) -> Result<Option<String>> {
+    Ok(Variable::get_by(b.conn(), |x| {
+        x.name() == name && x.environment() == env && !x.expired()
+    })?
+    .into_iter()
+    .find_map(|x| x.value().map(String::from)))
+}
+
+// The value of a credential variable, decrypted if it is secret or resolved
+// if it comes from the environment, a file, or a command
+fn variable(b: &mut Bastion, env: &str, name: Option<&str>) -> Result<String> {
+    // We can unwrap because the grant was validated when it was created
+    let name = name.unwrap();
+    let var = Variable::get_by(b.conn(), |x| x.name() == name && x.environment() == env)?
+        .into_iter()
+        .find(|x| x.value().is_some());
+    match var {
+        Some(var) if var.is_secret() => b.secret_key()?.decrypt(var.value().unwrap()),
+        Some(var) => var.resolve(),
+        None => Err(Error::new(ErrorKind::MissingOptions(vec![String::from(
+            name,
+        )]))),
+    }
+}
+
+// Remove the cached tokens of an environment
+pub fn clear_tokens(b: &Bastion, env: &str) -> Result<()> {
+    for var in Variable::get_by(b.conn(), |x| {
+        x.environment() == env
+            && x.source() == Some(OAuth2::SOURCE)
+            && (x.name() == OAuth2::ACCESS_TOKEN_VARIABLE
+                || x.name() == OAuth2::REFRESH_TOKEN_VARIABLE)
+    })? {
+        var.delete(b.conn())?;
+    }
+    Ok(())
+}
+
+// Set the access token of a request that uses OAuth2. Returns whether the
+// request uses OAuth2.
+pub fn authorize(b: &mut Bastion, req: &mut Request) -> Result<bool> {
+    if !req.auth().is_some_and(|x| x.is_oauth2()) {
+        return Ok(false);
+    }
+    let env = match b.current_environment() {
+        Some(x) => String::from(x),
+        None => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "The request uses OAuth2 and must be ran from an environment.",
+            )))
+        }
+    };
+    let token = access_token(b, &env, false)?;
+    req.set_input_option(OAuth2::ACCESS_TOKEN_VARIABLE, vec![&token])?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::PrintableTableStruct;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+    use tempfile::TempDir;
+
+    const ENV: &str = "test";
+
+    // A token endpoint that answers every request with respond, called
+    // with the form body. Returns its URL and the bodies it received.
+    fn token_server<F>(respond: F) -> (String, Arc<Mutex<Vec<String>>>)
+    where
+        F: Fn(&str) -> (u16, String) + Send + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/token", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(vec![]));
+        let bodies = received.clone();
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut len = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    if line.trim().is_empty() {
+                        break;
+                    }
+                    let line = line.to_lowercase();
+                    if let Some(x) = line.strip_prefix("content-length:") {
+                        len = x.trim().parse().unwrap();
+                    }
+                }
+                let mut body = vec![0; len];
+                reader.read_exact(&mut body).unwrap();
+                let body = String::from_utf8(body).unwrap();
+                let (status, resp) = respond(&body);
+                bodies.lock().unwrap().push(body);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} X\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    resp.len(),
+                    resp
+                )
+                .unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn setup(token_url: &str) -> (TempDir, Bastion) {
+        let dir = TempDir::new().unwrap();
+        let b = Bastion::new(dir.path().to_path_buf(), false).unwrap();
+        Variable::new("client-id", ENV, Some("id"), Some(Variable::USER))
+            .create(b.conn())
+            .unwrap();
+        OAuth2::new(ENV, token_url, OAuth2::CLIENT_CREDENTIALS, "client-id")
+            .create(b.conn())
+            .unwrap();
+        (dir, b)
+    }
+
+    fn grant_types(bodies: &Arc<Mutex<Vec<String>>>) -> Vec<String> {
+        bodies
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|body| {
+                body.split('&')
+                    .find_map(|x| x.strip_prefix("grant_type="))
+                    .map(String::from)
+                    .unwrap_or_default()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn access_token_is_cached() {
+        let (url, bodies) = token_server(|_| {
+            (
+                200,
+                String::from(r#"{"access_token":"t1","expires_in":3600}"#),
+            )
+        });
+        let (_dir, mut b) = setup(&url);
+
+        assert_eq!(access_token(&mut b, ENV, false).unwrap(), "t1");
+        assert_eq!(access_token(&mut b, ENV, false).unwrap(), "t1");
+        assert_eq!(bodies.lock().unwrap().len(), 1);
+
+        // renewing ignores the cached token
+        access_token(&mut b, ENV, true).unwrap();
+        assert_eq!(bodies.lock().unwrap().len(), 2);
+    }
+
+    #[test]
+    fn access_token_expires_within_margin() {
+        let expires_in = Arc::new(Mutex::new(EXPIRY_MARGIN));
+        let expiry = expires_in.clone();
+        let (url, bodies) = token_server(move |_| {
+            let body = format!(
+                r#"{{"access_token":"t1","expires_in":{}}}"#,
+                expiry.lock().unwrap()
+            );
+            (200, body)
+        });
+        let (_dir, mut b) = setup(&url);
+
+        // a token that expires within the margin is renewed
+        access_token(&mut b, ENV, false).unwrap();
+        access_token(&mut b, ENV, false).unwrap();
+        assert_eq!(bodies.lock().unwrap().len(), 2);
+
+        *expires_in.lock().unwrap() = EXPIRY_MARGIN + 60;
+        access_token(&mut b, ENV, false).unwrap();
+        access_token(&mut b, ENV, false).unwrap();
+        assert_eq!(bodies.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn access_token_uses_refresh_token() {
+        let (url, bodies) = token_server(|body| {
+            let token = match body.contains("grant_type=refresh_token") {
+                true => "t2",
+                false => "t1",
+            };
+            let body = format!(
+                r#"{{"access_token":"{}","expires_in":0,"refresh_token":"r1"}}"#,
+                token
+            );
+            (200, body)
+        });
+        let (_dir, mut b) = setup(&url);
+
+        assert_eq!(access_token(&mut b, ENV, false).unwrap(), "t1");
+        assert_eq!(access_token(&mut b, ENV, false).unwrap(), "t2");
+        assert_eq!(
+            grant_types(&bodies),
+            vec![OAuth2::CLIENT_CREDENTIALS, OAuth2::REFRESH_TOKEN]
+        );
+        assert!(bodies.lock().unwrap()[1].contains("refresh_token=r1"));
+    }
+
+    #[test]
+    fn access_token_falls_back_to_grant() {
+        let (url, bodies) = token_server(|body| match body.contains("grant_type=refresh_token") {
+            true => (400, String::from(r#"{"error":"invalid_grant"}"#)),
+            false => (
+                200,
+                String::from(r#"{"access_token":"t1","expires_in":0,"refresh_token":"r1"}"#),
+            ),
+        });
+        let (_dir, mut b) = setup(&url);
+
+        access_token(&mut b, ENV, false).unwrap();
+        assert_eq!(access_token(&mut b, ENV, false).unwrap(), "t1");
+        assert_eq!(
+            grant_types(&bodies),
+            vec![
+                OAuth2::CLIENT_CREDENTIALS,
+                OAuth2::REFRESH_TOKEN,
+                OAuth2::CLIENT_CREDENTIALS
+            ]
+        );
+    }
+
+    #[test]
+    fn cached_tokens_are_masked() {
+        let (url, _) = token_server(|_| {
+            (
+                200,
+                String::from(r#"{"access_token":"t1","refresh_token":"r1"}"#),
+            )
+        });
+        let (_dir, mut b) = setup(&url);
+        access_token(&mut b, ENV, false).unwrap();
+
+        let vars = Variable::get_by(b.conn(), |x| x.source() == Some(OAuth2::SOURCE)).unwrap();
+        assert_eq!(vars.len(), 2);
+        for var in vars {
+            let row = format!("{:?}", var.get_rows());
+            assert!(!row.contains("t1") && !row.contains("r1"));
+        }
+    }
+}
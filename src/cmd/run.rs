@@ -1,17 +1,67 @@
 use crate::bastion::Bastion;
-use crate::db::{DbObject, InputOption, OutputOption, Request, RequestResponse, Variable};
+use crate::db::auth::MASK;
+use crate::db::secret;
+use crate::db::{
+    Assertion, Auth, Config, DbObject, InputOption, OutputOption, Request, RequestResponse,
+    Setting, Variable, WorkflowStep,
+};
 use crate::error::{Error, ErrorKind, Result};
+use chrono::{DateTime, Utc};
 use clap_v3::ArgMatches;
 use colored::*;
 use regex::Regex;
-use reqwest::blocking;
-use reqwest::header::HeaderMap;
-use reqwest::Method;
+use reqwest::header::{HeaderMap, HeaderValue, AUTHORIZATION};
+use reqwest::StatusCode;
+use reqwest::{blocking, redirect, Certificate, Identity, Proxy};
+use rusqlite::Connection;
 use serde_json::Value;
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::io::Write;
 use std::process::{Command, Stdio};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Verbosity {
+    // print the request, response, and extractions
+    Normal,
+    // print only the response body
+    Quiet,
+    // print only assertion results
+    Silent,
+}
+
+// Options that control how the planned requests are sent and displayed
+#[derive(Clone, Copy)]
+pub struct RunOptions {
+    pub verbosity: Verbosity,
+    pub no_pager: bool,
+    // maximum number of requests in flight
+    pub parallel: usize,
+}
+
+impl RunOptions {
+    pub fn new(verbosity: Verbosity) -> RunOptions {
+        RunOptions {
+            verbosity,
+            no_pager: true,
+            parallel: 1,
+        }
+    }
+}
+
+// The parts of a response that are kept once the body has been read
+struct Received {
+    status: StatusCode,
+    headers: HeaderMap,
+    body: Vec<u8>,
+    sent: DateTime<Utc>,
+    ttfb: Duration,
+    elapsed: Duration,
+}
 
 pub fn execute(b: &mut Bastion, matches: &ArgMatches, req: Option<&str>) -> Result<()> {
     let req = req.or(b.current_request());
@@ -27,17 +77,40 @@ pub fn execute(b: &mut Bastion, matches: &ArgMatches, req: Option<&str>) -> Resu
     };
     let mut req = req.remove(0);
 
-    // get options for this request
-    let output_opts = req.output_options().clone();
+    let verbosity = match matches.is_present("quiet") {
+        true => Verbosity::Quiet,
+        false => Verbosity::Normal,
+    };
 
-    // if this request has extractions, check if there is an environment
-    if output_opts.len() > 0 && b.current_environment().is_none() {
-        return Err(Error::new(ErrorKind::ArgumentError(
-            "The request contains extractions and must be ran from an environment.",
-        )));
+    // run the requests that produce missing options first
+    let mut dependencies = vec![];
+    if matches.is_present("resolve") || b.interactive() {
+        resolve_dependencies(b.conn(), &req, &mut vec![], &mut dependencies)?;
+    }
+    if !dependencies.is_empty() {
+        let chain = format!("{} -> {}", dependencies.join(" -> "), req.name());
+        if matches.is_present("resolve")
+            || b.confirm(&format!(
+                "Missing options can be resolved by running {}. Continue?",
+                chain
+            ))?
+        {
+            println!("{}\n", format!("Resolved dependencies: {}", chain).bold());
+            for name in dependencies.iter() {
+                println!("{}\n", format!("[{}]", name).bold());
+                let dependency = Request::get_by_name(b.conn(), name)?.remove(0);
+                run_request(b, &dependency, RunOptions::new(verbosity))?;
+                println!();
+            }
+            // reload the request to pick up the extracted values
+            req = Request::get_by_name(b.conn(), req.name())?.remove(0);
+        }
     }
 
     // modify the request object given run arguments
+    if let Some(combination) = matches.value_of("combine") {
+        req.set_combination(Some(combination));
+    }
     if let Some(data) = matches.values_of("data") {
         for data in data {
             if req.body().is_none() {
@@ -56,8 +129,75 @@ pub fn execute(b: &mut Bastion, matches: &ArgMatches, req: Option<&str>) -> Resu
         }
     }
 
+    let mut opts = RunOptions::new(verbosity);
+    opts.no_pager = matches.is_present("no-pager");
+    if let Some(x) = matches.value_of("parallel") {
+        opts.parallel = x.parse()?;
+    }
+    let rrs = run_request(b, &req, opts)?;
+
+    if rrs.len() > 1 {
+        println!("\n  Summary");
+        super::show::print_table(RequestResponse::get_history(
+            b.conn(),
+            Some(req.name()),
+            b.current_environment(),
+            None,
+            Some(rrs.len() as u32),
+        )?);
+        println!();
+    }
+
+    let failed = rrs
+        .iter()
+        .flat_map(|rr| rr.assertion_results())
+        .filter(|(_, result)| *result != "pass")
+        .count();
+    if failed > 0 {
+        return Err(Error::new(ErrorKind::AssertionsFailed(failed)));
+    }
+    Ok(())
+}
+
+// Send all planned requests, evaluate assertions, extract output options,
+// and store the responses in the history.
+pub fn run_request(
+    b: &mut Bastion,
+    req: &Request,
+    opts: RunOptions,
+) -> Result<Vec<RequestResponse>> {
+    // get options for this request
+    let output_opts = req.output_options().clone();
+    let assertions = Assertion::get_by_name(b.conn(), req.name())?;
+
+    // if this request has extractions, check if there is an environment
+    if !output_opts.is_empty() && b.current_environment().is_none() {
+        return Err(Error::new(ErrorKind::ArgumentError(
+            "The request contains extractions and must be ran from an environment.",
+        )));
+    }
+
+    // fetch the access token before the requests are planned
+    let mut req = req.clone();
+    let oauth2 = super::oauth2::authorize(b, &mut req)?;
+    // resolve secret variables and variables read from the environment,
+    // files, or commands. Secret values are masked wherever the request is
+    // shown.
+    let mut secrets = req.secret_option_names();
+    let mut plaintexts = match req.has_encrypted_options() {
+        true => req.decrypt_input_options(b.secret_key()?)?,
+        false => vec![],
+    };
+    for (name, value, secret) in resolve_sources(b, &mut req)? {
+        if secret {
+            secrets.insert(name);
+            plaintexts.push(value);
+        }
+    }
+    let req = &req;
+
     // create all request objects
-    let requests = create_requests(&req)?;
+    let requests = create_requests(req)?;
 
     // delete extractions
     for opt in output_opts.iter() {
@@ -70,158 +210,565 @@ pub fn execute(b: &mut Bastion, matches: &ArgMatches, req: Option<&str>) -> Resu
         }
     }
 
-    RequestResponse::delete_all(b.conn())?;
-    let quiet = matches.is_present("quiet");
-    let many_requests = requests.len() > 1;
+    let mut opts = opts;
+    opts.no_pager = opts.no_pager || requests.len() > 1;
+    let env = b.current_environment().map(String::from);
+    let settings = resolve_settings(b, env.as_deref(), Some(req.name()))?;
+    let client = client_from_settings(&settings)?;
+    if is_insecure(&settings) && opts.verbosity != Verbosity::Silent {
+        println!(
+            "{}\n",
+            "[!] TLS certificate verification is disabled.".red().bold()
+        );
+    }
+    let mut planned = vec![];
     for mut req in requests {
-        let reqw = create_reqwest(&mut req)?;
+        let reqw = create_reqwest(&client, &mut req)?;
+        let mut rr = RequestResponse::new(req.name(), b.current_environment(), &reqw);
+        if let Some(auth) = req.auth() {
+            rr.mask_auth(auth);
+        }
+        rr.mask_values(&plaintexts);
+        for opt in req.input_options() {
+            let value = match secrets.contains(opt.option_name()) {
+                true => MASK,
+                false => opt.values().first().unwrap_or(&""),
+            };
+            rr.add_input_option(opt.option_name(), value);
+        }
+        planned.push((reqw, rr));
+    }
+
+    let mut rrs = vec![];
+    let mut error = None;
+    let mut renewed = false;
+    if opts.parallel <= 1 || planned.len() <= 1 {
+        for (reqw, rr) in planned {
+            if opts.verbosity == Verbosity::Normal {
+                print_request(&rr);
+            }
+            let retry = reqw.try_clone().filter(|_| oauth2);
+            let received = send(&client, reqw);
+            let received = retry_unauthorized(b, &client, received, retry, &mut renewed, opts)
+                .map_err(|x| mask_error(x, &plaintexts))?;
+            rrs.push(handle_response(b, req, &assertions, opts, rr, received)?);
+        }
+    } else {
+        // send concurrently, but display the responses in order
+        let total = planned.len();
+        let (requests, planned): (Vec<_>, Vec<_>) = planned
+            .into_iter()
+            .map(|(reqw, rr)| {
+                let header = format_request(&rr);
+                (reqw, (header, rr))
+            })
+            .unzip();
+        let mut retries: Vec<_> = requests
+            .iter()
+            .map(|x| x.try_clone().filter(|_| oauth2))
+            .collect();
+        let mut planned = planned.into_iter();
+        let mut pending = BTreeMap::new();
+        let mut next = 0;
+        for (i, received) in send_all(&client, requests, opts.parallel) {
+            pending.insert(i, received);
+            while let Some(received) = pending.remove(&next) {
+                // We can unwrap because there is a planned request for every result
+                let (header, rr) = planned.next().unwrap();
+                if opts.verbosity == Verbosity::Normal {
+                    println!("{}", format!("[{}/{}]", next + 1, total).bold());
+                    print!("{}", header);
+                }
+                let retry = retries[next].take();
+                next += 1;
+                match retry_unauthorized(b, &client, received, retry, &mut renewed, opts) {
+                    Ok(received) => {
+                        rrs.push(handle_response(b, req, &assertions, opts, rr, received)?)
+                    }
+                    Err(x) => {
+                        let x = mask_error(x, &plaintexts);
+                        println!("[!] {}\n", x);
+                        error = error.or(Some(x));
+                    }
+                }
+            }
+        }
+    }
+    prune_history(b)?;
+
+    b.set_completions()?;
+    match error {
+        Some(x) => Err(x),
+        None => Ok(rrs),
+    }
+}
 
-        if !quiet {
+// Display the response, check assertions, extract output options, and store
+// the response in the history.
+fn handle_response(
+    b: &mut Bastion,
+    req: &Request,
+    assertions: &[Assertion],
+    opts: RunOptions,
+    mut rr: RequestResponse,
+    received: Received,
+) -> Result<RequestResponse> {
+    let output_opts = req.output_options();
+    let verbosity = opts.verbosity;
+
+    // output response code and headers
+    if verbosity == Verbosity::Normal {
+        println!("{}", format!("< {}", received.status).bright_black());
+        for header in received.headers.iter() {
+            let (name, value) = header;
             println!(
                 "{}",
-                format!("> {} {}", reqw.method(), reqw.url()).bright_black()
+                format!("< {}: {}", name, value.to_str().unwrap()).bright_black()
             );
-            for header in reqw.headers() {
-                let (name, value) = header;
-                println!(
-                    "{}",
-                    format!("> {}: {}", name, value.to_str().unwrap()).bright_black()
-                );
-            }
-            println!();
         }
+        println!();
+    }
+
+    // output body with missing-newline indicator
+    rr.set_response(received.status, &received.headers, &received.body);
+    rr.set_timing(received.sent, received.ttfb, received.elapsed);
+    let text = String::from_utf8_lossy(&received.body).into_owned();
+
+    if verbosity != Verbosity::Silent {
+        display_body(&text, opts.no_pager);
+    }
+    if !output_opts.is_empty() && verbosity != Verbosity::Silent {
+        println!();
+    }
 
-        let mut rr = RequestResponse::new(&reqw);
-        let mut resp = blocking::Client::new().execute(reqw)?;
+    // check the response against the assertions
+    for assertion in assertions.iter() {
+        let failure = super::assert::evaluate(
+            assertion,
+            received.status,
+            &received.headers,
+            &text,
+            received.elapsed,
+        );
+        rr.add_assertion_result(&assertion.description(), failure.as_deref());
+    }
+    if !assertions.is_empty() {
+        for (description, result) in rr.assertion_results() {
+            super::assert::print_result(description, result);
+        }
+        if verbosity != Verbosity::Silent {
+            println!();
+        }
+    }
 
-        // output response code and headers
-        if !quiet {
-            println!("{}", format!("< {}", resp.status()).bright_black());
-            for header in resp.headers() {
-                let (name, value) = header;
+    // extract options into variables
+    for opt in output_opts.iter() {
+        let vars = match opt.extraction_type() {
+            "body" => body_to_vars(opt, &text, b.current_environment().unwrap()),
+            "header" => header_to_vars(opt, &received.headers, b.current_environment().unwrap()),
+            x => {
+                println!("Encountered unexpected source: {}.", x);
+                continue;
+            }
+        };
+        if let Err(x) = vars {
+            println!("[!] {}", x);
+            continue;
+        }
+        for var in vars.unwrap().iter_mut() {
+            var.set_source(Some(req.name()));
+            rr.add_extraction(var.name(), var.value().unwrap_or(""));
+            if verbosity == Verbosity::Normal {
                 println!(
                     "{}",
-                    format!("< {}: {}", name, value.to_str().unwrap()).bright_black()
+                    format!("{} <= {}", var.name(), var.value().unwrap_or("")).bright_black()
                 );
             }
-            println!();
-        }
 
-        // output body with missing-newline indicator
-        let mut text: Vec<u8> = vec![];
-        resp.copy_to(&mut text)?;
-        rr.set_response(&resp, &text);
-        let text = String::from_utf8(text).unwrap();
+            // delete variables that have the same name, environment, and value
+            let vars = Variable::get_by(b.conn(), |v| {
+                v.name() == var.name()
+                    && v.environment() == var.environment()
+                    && v.value() == var.value()
+            })?;
+            for v in vars {
+                v.delete(b.conn())?;
+            }
 
-        display_body(&text, many_requests || matches.is_present("no-pager"));
-        if output_opts.len() > 0 {
-            println!();
+            var.create(b.conn())?;
+            b.set_options(InputOption::get_by(b.conn(), |x| {
+                x.option_name() == var.name()
+            })?)?;
         }
+    }
+    rr.create(b.conn())?;
+    RequestResponse::get_by_id(b.conn(), b.conn().last_insert_rowid() as u32)
+}
 
-        // extract options into variables
-        for opt in output_opts.iter() {
-            let vars = match opt.extraction_type() {
-                "body" => body_to_vars(&opt, &text, b.current_environment().unwrap()),
-                "header" => header_to_vars(&opt, resp.headers(), b.current_environment().unwrap()),
-                x => {
-                    println!("Encountered unexpected source: {}.", x);
-                    continue;
+// Format a request as it is stored, with credentials masked
+fn format_request(rr: &RequestResponse) -> String {
+    let mut lines = vec![format!("> {} {}", rr.method(), rr.url())];
+    for header in rr.request_headers().into_iter().filter(|x| !x.is_empty()) {
+        lines.push(format!("> {}", header));
+    }
+    format!("{}\n\n", lines.join("\n").bright_black())
+}
+// Resend a request with a renewed access token if the server rejected its
+// token. The token is renewed once per run; later retries reuse it.
+fn retry_unauthorized(
+    b: &mut Bastion,
+    client: &blocking::Client,
+    received: Result<Received>,
+    retry: Option<blocking::Request>,
+    renewed: &mut bool,
+    opts: RunOptions,
+) -> Result<Received> {
+    let mut retry = match (&received, retry) {
+        (Ok(x), Some(retry)) if x.status == StatusCode::UNAUTHORIZED => retry,
+        _ => return received,
+    };
+    if !*renewed && opts.verbosity != Verbosity::Silent {
+        println!(
+            "{}\n",
+            "[!] The access token was rejected. Requesting a new one.".bright_black()
+        );
+    }
+    // We can unwrap because OAuth2 requests are only sent from an environment
+    let env = String::from(b.current_environment().unwrap());
+    let token = super::oauth2::access_token(b, &env, !*renewed)?;
+    *renewed = true;
+    let value = HeaderValue::from_str(&format!("Bearer {}", token))
+        .map_err(|_| Error::new(ErrorKind::ParseError))?;
+    retry.headers_mut().insert(AUTHORIZATION, value);
+    send(client, retry)
+}
+
+// Errors from sending a request include its URL, which may hold secret or
+// resolved values
+fn mask_error(err: Error, plaintexts: &[String]) -> Error {
+    if plaintexts.is_empty() {
+        return err;
+    }
+    let message = RequestResponse::mask_str(&err.to_string(), plaintexts);
+    Error::new(ErrorKind::RequestFailed(message))
+}
+
+fn print_request(rr: &RequestResponse) {
+    print!("{}", format_request(rr));
+}
+
+fn send(client: &blocking::Client, reqw: blocking::Request) -> Result<Received> {
+    let sent = Utc::now();
+    let start = Instant::now();
+    let mut resp = client.execute(reqw)?;
+    let ttfb = start.elapsed();
+    let mut body: Vec<u8> = vec![];
+    resp.copy_to(&mut body)?;
+    Ok(Received {
+        status: resp.status(),
+        headers: resp.headers().clone(),
+        body,
+        sent,
+        ttfb,
+        elapsed: start.elapsed(),
+    })
+}
+
+// Send requests from a bounded pool of worker threads. Results are tagged
+// with the index of their request and arrive in completion order.
+fn send_all(
+    client: &blocking::Client,
+    requests: Vec<blocking::Request>,
+    workers: usize,
+) -> mpsc::Receiver<(usize, Result<Received>)> {
+    // there is no use for more workers than requests
+    let workers = workers.min(requests.len()).max(1);
+    let queue = Arc::new(Mutex::new(requests.into_iter().enumerate()));
+    let (tx, rx) = mpsc::channel();
+    for _ in 0..workers {
+        let (queue, tx, client) = (queue.clone(), tx.clone(), client.clone());
+        thread::spawn(move || loop {
+            let job = queue.lock().unwrap().next();
+            match job {
+                Some((i, reqw)) => {
+                    if tx.send((i, send(&client, reqw))).is_err() {
+                        break;
+                    }
                 }
-            };
-            if let Err(x) = vars {
-                println!("[!] {}", x);
-                continue;
+                None => break,
             }
-            for var in vars.unwrap().iter_mut() {
-                var.set_source(Some(req.name()));
-                rr.add_extraction(var.name(), var.value().unwrap_or(""));
-                if !quiet {
-                    println!(
-                        "{}",
-                        format!("{} <= {}", var.name(), var.value().unwrap_or("")).bright_black()
-                    );
-                }
+        });
+    }
+    rx
+}
 
-                // delete variables that have the same name, environment, and value
-                let vars = Variable::get_by(b.conn(), |v| {
-                    v.name() == var.name()
-                        && v.environment() == var.environment()
-                        && v.value() == var.value()
-                })?;
-                for v in vars {
-                    v.delete(b.conn())?;
-                }
+// Find the requests that extract the missing input options of req, in the
+// order they need to run. Options without a producer are left for
+// create_requests to report.
+fn resolve_dependencies(
+    conn: &Connection,
+    req: &Request,
+    stack: &mut Vec<String>,
+    order: &mut Vec<String>,
+) -> Result<()> {
+    stack.push(String::from(req.name()));
+    for opt in req.input_options().iter().filter(|x| x.is_missing()) {
+        // if multiple requests produce the option, use the first by name.
+        // A request that extracts its own option cannot provide it.
+        let mut producers: Vec<String> = OutputOption::get_by(conn, |x| {
+            x.option_name() == opt.option_name() && x.request_name() != req.name()
+        })?
+        .iter()
+        .map(|x| String::from(x.request_name()))
+        .collect();
+        producers.sort();
+        let producer = match producers.first() {
+            Some(x) => x,
+            None => continue,
+        };
 
-                var.create(b.conn())?;
-                b.set_options(InputOption::get_by(b.conn(), |x| {
-                    x.option_name() == var.name()
-                })?)?;
-            }
+        if let Some(i) = stack.iter().position(|x| x == producer) {
+            let mut cycle = stack[i..].to_vec();
+            cycle.push(producer.clone());
+            return Err(Error::new(ErrorKind::DependencyCycle(cycle)));
+        }
+        if order.contains(producer) {
+            continue;
         }
-        rr.create(b.conn())?;
+        let mut producer_req = Request::get_by_name(conn, producer)?;
+        if producer_req.is_empty() {
+            return Err(Error::new(ErrorKind::NotFound));
+        }
+        resolve_dependencies(conn, &producer_req.remove(0), stack, order)?;
+        order.push(producer.clone());
     }
+    stack.pop();
+    Ok(())
+}
 
-    if many_requests {
-        println!("\n  Summary");
-        super::show::print_table(RequestResponse::get_all(b.conn())?);
+pub fn workflow(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because name is required
+    let name = matches.value_of("name").unwrap();
+    let steps = WorkflowStep::get_by_name(b.conn(), name)?;
+    if steps.is_empty() {
+        return Err(Error::new(ErrorKind::NotFound));
+    }
+    let verbosity = match matches.is_present("quiet") {
+        true => Verbosity::Quiet,
+        false => Verbosity::Normal,
+    };
+
+    let mut rrs = vec![];
+    let mut failure = None;
+    for step in steps.iter() {
+        println!(
+            "{}\n",
+            format!("[{}/{}] {}", step.step(), steps.len(), step.request_name()).bold()
+        );
+        let result = run_step(b, step, verbosity);
         println!();
+        match result {
+            Ok((step_rrs, reason)) => {
+                rrs.extend(step_rrs);
+                if let Some(reason) = reason {
+                    failure = Some((step, reason));
+                    break;
+                }
+            }
+            Err(x) => {
+                failure = Some((step, format!("{}", x)));
+                break;
+            }
+        }
     }
 
-    b.set_completions()?;
-    Ok(())
+    println!("  Summary");
+    super::show::print_table(rrs);
+    println!();
+
+    match failure {
+        Some((step, reason)) => Err(Error::new(ErrorKind::WorkflowFailed(format!(
+            "step {} ({}) {}",
+            step.step(),
+            step.request_name(),
+            reason
+        )))),
+        None => Ok(()),
+    }
+}
+
+// Run a single workflow step. Returns the responses and the reason the step
+// failed, if it did.
+fn run_step(
+    b: &mut Bastion,
+    step: &WorkflowStep,
+    verbosity: Verbosity,
+) -> Result<(Vec<RequestResponse>, Option<String>)> {
+    let mut req = Request::get_by_name(b.conn(), step.request_name())?;
+    if req.is_empty() {
+        return Ok((vec![], Some(String::from("does not exist"))));
+    }
+    let mut req = req.remove(0);
+    for (opt, values) in step.options() {
+        if req.set_input_option(opt, values).is_err() {
+            return Ok((vec![], Some(format!("has no option '{}'", opt))));
+        }
+    }
+
+    let rrs = run_request(b, &req, RunOptions::new(verbosity))?;
+    let mut reason = None;
+    for rr in rrs.iter() {
+        let status = rr.status().unwrap_or("-");
+        if !status.starts_with('2') && !status.starts_with('3') {
+            reason = Some(format!("returned {}", status));
+        } else if rr.assertion_results().iter().any(|x| x.1 != "pass") {
+            reason = Some(String::from("failed assertions"));
+        } else if let Some(opt) = req
+            .output_options()
+            .iter()
+            .find(|opt| !rr.extractions().iter().any(|x| x.0 == opt.option_name()))
+        {
+            reason = Some(format!("could not extract '{}'", opt.option_name()));
+        }
+        if reason.is_some() {
+            break;
+        }
+    }
+    Ok((rrs, reason))
+}
+
+pub fn prune_history(b: &Bastion) -> Result<()> {
+    // keep the last 1000 responses by default; 0 disables the limit
+    let max_rows = match Setting::get_value(b.conn(), Setting::HISTORY_MAX_ROWS)? {
+        Some(x) => x.parse()?,
+        None => 1000,
+    };
+    let max_age = match Setting::get_value(b.conn(), Setting::HISTORY_MAX_AGE)? {
+        Some(x) => Some(super::cmd::parse_duration(&x)?),
+        None => None,
+    };
+    let max_rows = match max_rows {
+        0 => None,
+        x => Some(x),
+    };
+    RequestResponse::prune(b.conn(), max_rows, max_age)
 }
 
 // TODO: make this a method of Request
+// Replace the values of input options that come from variables with an
+// env:, file:, or cmd: source by what they resolve to now. Returns the
+// option names, resolved values, and whether the variables are secret.
+pub fn resolve_sources(b: &Bastion, req: &mut Request) -> Result<Vec<(String, String, bool)>> {
+    let env = match b.current_environment() {
+        Some(x) => x,
+        None => return Ok(vec![]),
+    };
+    let mut resolved = vec![];
+    for var in Variable::get_by(b.conn(), |x| x.environment() == env && x.is_dynamic())? {
+        let opt = req
+            .input_options()
+            .iter()
+            .find(|x| x.option_name() == var.name())
+            .cloned();
+        if let Some(opt) = opt {
+            let value = var.resolve()?;
+            let values = opt
+                .values()
+                .into_iter()
+                .map(|x| match Some(x) == var.value() {
+                    true => value.as_str(),
+                    false => x,
+                })
+                .collect();
+            req.set_input_option(var.name(), values)?;
+            resolved.push((String::from(var.name()), value, var.is_secret()));
+        }
+    }
+    Ok(resolved)
+}
+
 pub fn create_requests(req: &Request) -> Result<Vec<Request>> {
-    let input_opts = req.input_options();
-    let missing_opts: Vec<_> = input_opts
+    build_requests(req, true)
+}
+
+// The planned requests with built-in placeholders such as {$uuid} left
+// unexpanded, for display
+pub fn planned_requests(req: &Request) -> Result<Vec<Request>> {
+    build_requests(req, false)
+}
+
+fn build_requests(req: &Request, expand: bool) -> Result<Vec<Request>> {
+    let replace = |req: &mut Request| match expand {
+        true => req.replace_input_options(),
+        false => req.substitute_input_options(),
+    };
+    let missing_opts: Vec<_> = req
+        .input_options()
         .iter()
-        .filter(|opt| opt.values().len() == 0)
+        .filter(|opt| opt.is_missing())
         .map(|opt| String::from(opt.option_name()))
         .collect();
-    if missing_opts.len() > 0 {
-        // All input options are required
+    if !missing_opts.is_empty() {
+        // Options without a default or '?' are required
         return Err(Error::new(ErrorKind::MissingOptions(missing_opts)));
     }
+    // optional options without a value are left out of the combinations
+    let input_opts: Vec<&InputOption> = req
+        .input_options()
+        .iter()
+        .filter(|opt| !opt.effective_values().is_empty())
+        .collect();
 
-    if input_opts.len() == 0 {
+    if input_opts.is_empty() {
         let mut req = req.clone();
-        req.replace_input_options()?;
+        replace(&mut req)?;
         return Ok(vec![req]);
     }
 
     let mut requests = Vec::new();
-    let opts: Vec<_> = input_opts.iter().map(|opt| opt.values()).collect();
+    let opts: Vec<_> = input_opts
+        .iter()
+        .map(|opt| opt.effective_values())
+        .collect();
 
-    for i in 0..opts.iter().map(|x| x.len()).max().unwrap_or(0) {
-        let opt_values: Vec<&str> = opts.iter().map(|v| v[i % v.len()]).collect();
-        let mut opts = input_opts.clone();
+    let combinations: Vec<Vec<&str>> = match req.combination() {
+        "first" => vec![opts.iter().map(|v| v[0]).collect()],
+        "product" => {
+            // the last option changes fastest
+            let mut combinations = vec![vec![]];
+            for values in opts.iter() {
+                let mut next = vec![];
+                for combination in combinations.iter() {
+                    for value in values.iter() {
+                        let mut combination: Vec<&str> = combination.clone();
+                        combination.push(value);
+                        next.push(combination);
+                    }
+                }
+                combinations = next;
+            }
+            combinations
+        }
+        // zip values together, repeating shorter lists
+        _ => (0..opts.iter().map(|x| x.len()).max().unwrap_or(0))
+            .map(|i| opts.iter().map(|v| v[i % v.len()]).collect())
+            .collect(),
+    };
+
+    for opt_values in combinations {
         let mut req = req.clone();
-        for (opt, opt_value) in opts.iter_mut().zip(opt_values) {
+        for (opt, opt_value) in input_opts.iter().zip(opt_values) {
             req.set_input_option(opt.option_name(), vec![opt_value])?;
         }
-        req.replace_input_options()?;
+        replace(&mut req)?;
         requests.push(req);
     }
     Ok(requests)
 }
 
-fn create_reqwest(req: &mut Request) -> Result<blocking::Request> {
+pub fn create_reqwest(client: &blocking::Client, req: &mut Request) -> Result<blocking::Request> {
     // TODO: should this be a method of Request?
-    let client = blocking::Client::new();
-    let mut builder = match req.method() {
-        &Method::GET => client.get(req.url()),
-        &Method::POST => client.post(req.url()),
-        &Method::PUT => client.put(req.url()),
-        &Method::PATCH => client.patch(req.url()),
-        &Method::DELETE => client.delete(req.url()),
-        &Method::HEAD => client.head(req.url()),
-        _ => {
-            return Err(Error::new(ErrorKind::ArgumentError(
-                "Method not supported.",
-            )));
-        }
-    };
+    let mut builder = client.request(req.method().clone(), req.url());
     // add headers
     if let Some(x) = req.headers() {
         for hv in x.split('\n') {
@@ -230,16 +777,171 @@ fn create_reqwest(req: &mut Request) -> Result<blocking::Request> {
             if header.and(value).is_none() {
                 continue;
             }
-            builder = builder.header(header.unwrap(), value.unwrap());
+            builder = builder.header(header.unwrap().trim(), value.unwrap().trim());
         }
     }
     // add body
     if let Some(x) = req.consume_body() {
         builder = builder.body(x);
     }
+    builder = match req.auth() {
+        Some(Auth::Basic(user, password)) => builder.basic_auth(user, Some(password)),
+        Some(Auth::Bearer(token)) | Some(Auth::OAuth2(token)) => builder.bearer_auth(token),
+        Some(Auth::ApiKeyHeader(name, key)) => builder.header(name.as_str(), key.as_str()),
+        Some(Auth::ApiKeyQuery(name, key)) => builder.query(&[(name, key)]),
+        None => builder,
+    };
+
+    Ok(builder.build()?)
+}
+
+// Build a HTTP client from the workspace settings and the overrides for the
+// environment and request.
+pub fn create_client(
+    b: &mut Bastion,
+    environment: Option<&str>,
+    request: Option<&str>,
+) -> Result<blocking::Client> {
+    client_from_settings(&resolve_settings(b, environment, request)?)
+}
+
+// The settings for the environment and request, with the client certificate
+// password decrypted
+pub fn resolve_settings(
+    b: &mut Bastion,
+    environment: Option<&str>,
+    request: Option<&str>,
+) -> Result<BTreeMap<String, String>> {
+    let mut settings = Config::resolve(b.conn(), environment, request)?;
+    if let Some(x) = settings.get_mut(Config::CLIENT_CERT_PASSWORD) {
+        if secret::is_encrypted(x) {
+            *x = b.secret_key()?.decrypt(x)?;
+        }
+    }
+    Ok(settings)
+}
+
+pub fn client_from_settings(settings: &BTreeMap<String, String>) -> Result<blocking::Client> {
+    let get = |key| settings.get(key).map(|x| x.as_str());
+    let mut builder = blocking::Client::builder();
+    if let Some(x) = get(Config::TIMEOUT) {
+        builder = builder.timeout(super::cmd::parse_std_duration(x)?);
+    }
+    if let Some(x) = get(Config::CONNECT_TIMEOUT) {
+        builder = builder.connect_timeout(super::cmd::parse_std_duration(x)?);
+    }
+    let max_redirects = match get(Config::MAX_REDIRECTS) {
+        Some(x) => x.parse()?,
+        None => 10,
+    };
+    let follow_redirects = match get(Config::FOLLOW_REDIRECTS) {
+        Some(x) => super::cmd::parse_bool(x)?,
+        None => true,
+    };
+    // reqwest counts the original URL towards the limit
+    builder = match (follow_redirects, max_redirects) {
+        (true, n) if n > 0 => builder.redirect(redirect::Policy::limited(n + 1)),
+        _ => builder.redirect(redirect::Policy::none()),
+    };
+    builder = match get(Config::PROXY) {
+        Some("none") => builder.no_proxy(),
+        Some(x) => builder.proxy(Proxy::all(x)?),
+        None => builder,
+    };
+    if let Some(x) = get(Config::USER_AGENT) {
+        builder = builder.user_agent(x);
+    }
+    // responses are not decompressed unless enabled
+    let gzip = match get(Config::GZIP) {
+        Some(x) => super::cmd::parse_bool(x)?,
+        None => false,
+    };
+    builder = builder.gzip(gzip);
+    // Only rustls offers HTTP/2 during the TLS handshake, so requests use
+    // HTTP/1.1 unless prior knowledge is set or a PEM identity is used
+    builder = match get(Config::HTTP_VERSION) {
+        Some("1.1") | None => builder,
+        Some("2") => builder.http2_prior_knowledge(),
+        Some(_) => {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "Expected HTTP version 1.1 or 2.",
+            )))
+        }
+    };
 
+    // TLS
+    if let Some(x) = get(Config::CA_CERT) {
+        let pem = fs::read_to_string(x)?;
+        let certs = pem_certificates(&pem);
+        if certs.is_empty() {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "No certificates found in the CA file.",
+            )));
+        }
+        for cert in certs {
+            builder = builder.add_root_certificate(Certificate::from_pem(cert.as_bytes())?);
+        }
+    }
+    // the key is read even without a certificate to catch a bad path early
+    let key = match get(Config::CLIENT_KEY) {
+        Some(x) => Some(fs::read(x)?),
+        None => None,
+    };
+    if let Some(x) = get(Config::CLIENT_CERT) {
+        let mut cert = fs::read(x)?;
+        builder = match cert.starts_with(b"-----") {
+            // PEM identities are only supported by rustls
+            true => {
+                if get(Config::HTTP_VERSION) == Some("1.1") {
+                    return Err(Error::new(ErrorKind::ArgumentError(
+                        "HTTP/1.1 cannot be enforced with a PEM client certificate.",
+                    )));
+                }
+                if let Some(key) = key {
+                    cert.push(b'\n');
+                    cert.extend(key);
+                }
+                builder
+                    .use_rustls_tls()
+                    .identity(Identity::from_pem(&cert)?)
+            }
+            false => {
+                let password = get(Config::CLIENT_CERT_PASSWORD).unwrap_or("");
+                builder.identity(Identity::from_pkcs12_der(&cert, password)?)
+            }
+        };
+    }
+    if let Some(x) = get(Config::INSECURE) {
+        builder = builder.danger_accept_invalid_certs(super::cmd::parse_bool(x)?);
+    }
     Ok(builder.build()?)
 }
+
+pub fn is_insecure(settings: &BTreeMap<String, String>) -> bool {
+    match settings.get(Config::INSECURE) {
+        Some(x) => super::cmd::parse_bool(x).unwrap_or(false),
+        None => false,
+    }
+}
+
+// Split a PEM bundle into its certificates
+fn pem_certificates(pem: &str) -> Vec<String> {
+    const BEGIN: &str = "-----BEGIN CERTIFICATE-----";
+    const END: &str = "-----END CERTIFICATE-----";
+    let mut certs = vec![];
+    let mut rest = pem;
+    while let Some(start) = rest.find(BEGIN) {
+        match rest[start..].find(END) {
+            Some(end) => {
+                let end = start + end + END.len();
+                certs.push(String::from(&rest[start..end]));
+                rest = &rest[end..];
+            }
+            None => break,
+        }
+    }
+    certs
+}
 fn body_to_vars(opt: &OutputOption, body: &str, env: &str) -> Result<Vec<Variable>> {
     let values = get_json_values(&serde_json::from_str(body)?, opt.extraction_source())?;
     Ok(values
@@ -257,7 +959,7 @@ fn header_to_vars(opt: &OutputOption, headers: &HeaderMap, env: &str) -> Result<
     // TODO: handle multiple headers
     Ok(vec![Variable::new(opt.option_name(), env, value, None)])
 }
-fn get_json_values(root: &Value, query: &str) -> Result<Vec<Value>> {
+pub fn get_json_values(root: &Value, query: &str) -> Result<Vec<Value>> {
     let mut v: Value = root.clone();
     let mut result: &mut Value = &mut v;
 
@@ -326,3 +1028,164 @@ pub fn display_body(text: &str, no_pager: bool) {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use std::io::{BufRead, BufReader};
+    use std::net::TcpListener;
+    use tempfile::TempDir;
+
+    fn create_request(conn: &Connection, name: &str, url: &str, extracts: &[&str]) {
+        Request::new(name, None, url).create(conn).unwrap();
+        for opt in extracts {
+            OutputOption::new(name, opt, "body", opt)
+                .create(conn)
+                .unwrap();
+        }
+    }
+
+    fn dependencies(conn: &Connection, name: &str) -> Result<Vec<String>> {
+        let req = Request::get_by_name(conn, name)?.remove(0);
+        let mut order = vec![];
+        resolve_dependencies(conn, &req, &mut vec![], &mut order)?;
+        Ok(order)
+    }
+
+    #[test]
+    fn dependencies_run_in_order() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::new(dir.path(), "test.db").unwrap();
+        let conn = db.conn();
+        create_request(conn, "get-a", "http://localhost/a", &["a-id"]);
+        create_request(conn, "get-b", "http://localhost/b/{a-id}", &["b-id"]);
+        create_request(conn, "get-c", "http://localhost/{a-id}/{b-id}", &[]);
+
+        assert_eq!(dependencies(conn, "get-c").unwrap(), vec!["get-a", "get-b"]);
+        assert!(dependencies(conn, "get-a").unwrap().is_empty());
+    }
+
+    #[test]
+    fn dependency_cycle() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::new(dir.path(), "test.db").unwrap();
+        let conn = db.conn();
+        create_request(conn, "get-x", "http://localhost/{y}", &["x"]);
+        create_request(conn, "get-y", "http://localhost/{x}", &["y"]);
+
+        assert_eq!(
+            dependencies(conn, "get-x").unwrap_err().to_string(),
+            "Found a dependency cycle: get-x -> get-y -> get-x"
+        );
+    }
+
+    #[test]
+    fn dependency_on_itself_is_ignored() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::new(dir.path(), "test.db").unwrap();
+        let conn = db.conn();
+        create_request(conn, "get-token", "http://localhost/{token}", &["token"]);
+
+        assert!(dependencies(conn, "get-token").unwrap().is_empty());
+    }
+
+    #[test]
+    fn dependency_on_missing_request() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::new(dir.path(), "test.db").unwrap();
+        let conn = db.conn();
+        create_request(conn, "get-a", "http://localhost/{id}", &[]);
+        // an extraction left behind by a request that no longer exists
+        conn.execute_batch("PRAGMA foreign_keys = OFF;").unwrap();
+        OutputOption::new("deleted", "id", "body", "id")
+            .create(conn)
+            .unwrap();
+
+        assert_eq!(
+            dependencies(conn, "get-a").unwrap_err().to_string(),
+            "Not found."
+        );
+    }
+
+    fn settings(pairs: &[(&str, &str)]) -> BTreeMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (String::from(*k), String::from(*v)))
+            .collect()
+    }
+
+    // The first line a client built from the settings sends to a server
+    fn request_line(pairs: &[(&str, &str)]) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/", listener.local_addr().unwrap());
+        let server = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut line = String::new();
+            BufReader::new(stream).read_line(&mut line).unwrap();
+            line
+        });
+        let mut pairs = pairs.to_vec();
+        pairs.push((Config::TIMEOUT, "1s"));
+        let client = client_from_settings(&settings(&pairs)).unwrap();
+        // the server never answers
+        assert!(client.get(&url).send().is_err());
+        server.join().unwrap()
+    }
+
+    #[test]
+    fn client_settings() {
+        let valid = settings(&[
+            (Config::TIMEOUT, "5s"),
+            (Config::CONNECT_TIMEOUT, "500ms"),
+            (Config::FOLLOW_REDIRECTS, "false"),
+            (Config::MAX_REDIRECTS, "3"),
+            (Config::PROXY, "none"),
+            (Config::USER_AGENT, "repost"),
+            (Config::GZIP, "true"),
+        ]);
+        assert!(client_from_settings(&valid).is_ok());
+        assert!(client_from_settings(&BTreeMap::new()).is_ok());
+
+        for invalid in &[
+            (Config::TIMEOUT, "soon"),
+            (Config::MAX_REDIRECTS, "-1"),
+            (Config::FOLLOW_REDIRECTS, "maybe"),
+            (Config::GZIP, "2"),
+            (Config::HTTP_VERSION, "3"),
+        ] {
+            assert!(client_from_settings(&settings(&[*invalid])).is_err());
+        }
+    }
+
+    #[test]
+    fn client_http_version() {
+        assert_eq!(request_line(&[]), "GET / HTTP/1.1\r\n");
+        assert_eq!(
+            request_line(&[(Config::HTTP_VERSION, "1.1")]),
+            "GET / HTTP/1.1\r\n"
+        );
+        assert_eq!(
+            request_line(&[(Config::HTTP_VERSION, "2")]),
+            "PRI * HTTP/2.0\r\n"
+        );
+    }
+
+    #[test]
+    fn client_pem_identity_with_http1() {
+        let dir = TempDir::new().unwrap();
+        let cert = dir.path().join("client.pem");
+        fs::write(&cert, "-----BEGIN CERTIFICATE-----\n").unwrap();
+        let cert = cert.to_str().unwrap();
+        let err = client_from_settings(&settings(&[
+            (Config::CLIENT_CERT, cert),
+            (Config::HTTP_VERSION, "1.1"),
+        ]))
+        .err()
+        .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "HTTP/1.1 cannot be enforced with a PEM client certificate."
+        );
+    }
+}
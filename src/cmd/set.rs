@@ -1,7 +1,9 @@
 use crate::bastion::Bastion;
-use crate::db::{DbObject, InputOption, Variable};
+use crate::db::{Config, DbObject, Environment, InputOption, Request, Setting, Variable};
 use crate::error::{Error, ErrorKind, Result};
 use clap_v3::ArgMatches;
+use std::collections::BTreeMap;
+use std::fs;
 
 pub fn variable(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     let name = matches.value_of("name").unwrap();
@@ -29,15 +31,100 @@ pub fn variable(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     let mut var = Variable::get_by_name_map(b.conn(), name, |e| String::from(e.environment()))?;
     for env_val in env_vals {
         let (environment, value) = env_val;
+        let source = Variable::user_source(&value);
         let mut var = var.remove(&environment).unwrap_or(Variable::new(
             name,
             &environment,
             Some(&value),
-            Some("user"),
+            Some(source),
         ));
+        if var.is_user_defined() {
+            var.set_source(Some(source));
+        }
+        // secret variables stay secret. Values read from env:, file:, or cmd:
+        // are not stored, so only what they resolve to is masked.
+        let secret = matches.is_present("secret") || var.is_secret();
+        var.set_secret(secret);
         var.set_value(Some(&value));
+        if secret && source == Variable::USER {
+            var.encrypt(b.secret_key()?);
+        }
         var.upsert(b.conn())?;
     }
     b.set_options(InputOption::get_by(b.conn(), |x| x.option_name() == name)?)?;
     Ok(())
 }
+
+pub fn retention(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    if let Some(max_rows) = matches.value_of("max-rows") {
+        max_rows.parse::<u32>()?;
+        Setting::new(Setting::HISTORY_MAX_ROWS, max_rows).upsert(b.conn())?;
+    }
+    if let Some(max_age) = matches.value_of("max-age") {
+        match max_age {
+            "none" => {
+                for setting in Setting::get_by_name(b.conn(), Setting::HISTORY_MAX_AGE)? {
+                    setting.delete(b.conn())?;
+                }
+            }
+            _ => {
+                super::cmd::parse_duration(max_age)?;
+                Setting::new(Setting::HISTORY_MAX_AGE, max_age).upsert(b.conn())?;
+            }
+        }
+    }
+    super::run::prune_history(b)?;
+
+    println!();
+    super::show::print_table(Setting::get_by(b.conn(), |x| {
+        x.key().starts_with("history.")
+    })?);
+    println!();
+    Ok(())
+}
+
+pub fn config(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
+    // We can unwrap because key and value are required
+    let key = matches.value_of("key").unwrap();
+    let value = matches.value_of("value").unwrap();
+    let environment = matches.value_of("environment");
+    let request = matches.value_of("request");
+    check_config_scope(b, environment, request)?;
+
+    // check the value by building a client with only this setting. A client
+    // certificate may depend on the key or password, so only check it can be read.
+    match key {
+        Config::CLIENT_CERT => {
+            fs::read(value)?;
+        }
+        _ => {
+            let mut settings = BTreeMap::new();
+            settings.insert(String::from(key), String::from(value));
+            super::run::client_from_settings(&settings)?;
+        }
+    }
+
+    // the password is encrypted like a secret variable
+    let value = match key {
+        Config::CLIENT_CERT_PASSWORD => b.secret_key()?.encrypt(value),
+        _ => String::from(value),
+    };
+    Config::new(key, &value, environment, request).upsert(b.conn())?;
+    Ok(())
+}
+
+fn check_config_scope(b: &Bastion, environment: Option<&str>, request: Option<&str>) -> Result<()> {
+    if let Some(environment) = environment {
+        if !Environment::exists(b.conn(), environment)? {
+            println!("Environment '{}' not found.", environment);
+            return Err(Error::new(ErrorKind::NotFound));
+        }
+    }
+    if let Some(request) = request {
+        if !Request::exists(b.conn(), request)? {
+            println!("Request '{}' not found.", request);
+            return Err(Error::new(ErrorKind::NotFound));
+        }
+    }
+    Ok(())
+}
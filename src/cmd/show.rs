@@ -1,6 +1,10 @@
 use crate::bastion::Bastion;
+use crate::db::request_response::{format_ms, format_size};
 use crate::db::PrintableTable;
-use crate::db::{DbObject, Environment, InputOption, Request, RequestResponse, Variable};
+use crate::db::{
+    Config, DbObject, Environment, InputOption, OAuth2, Request, RequestResponse, Variable,
+    WorkflowStep,
+};
 use crate::error::{Error, ErrorKind, Result};
 use clap_v3::ArgMatches;
 use colored::*;
@@ -15,22 +19,47 @@ pub fn requests(b: &Bastion, _matches: &ArgMatches) -> Result<()> {
     println!();
     Ok(())
 }
-pub fn variables(b: &Bastion, matches: &ArgMatches) -> Result<()> {
-    println!();
+pub fn variables(b: &mut Bastion, matches: &ArgMatches) -> Result<()> {
     let name = matches.value_of("name");
-    match (b.current_environment(), name) {
-        (Some(env), Some(name)) => print_table(Variable::get_by(b.conn(), |var| {
+    let mut vars = match (b.current_environment(), name) {
+        (Some(env), Some(name)) => Variable::get_by(b.conn(), |var| {
             var.environment() == env && var.name() == name
-        })?),
-        (Some(env), None) => {
-            print_table(Variable::get_by(b.conn(), |var| var.environment() == env)?)
+        })?,
+        (Some(env), None) => Variable::get_by(b.conn(), |var| var.environment() == env)?,
+        (None, Some(name)) => Variable::get_by_name(b.conn(), name)?,
+        (None, None) => Variable::get_all(b.conn())?,
+    };
+    if matches.is_present("reveal") && vars.iter().any(|x| x.is_secret()) {
+        let key = b.secret_key()?;
+        for var in vars.iter_mut() {
+            var.decrypt(key)?;
         }
-        (None, Some(name)) => print_table(Variable::get_by_name(b.conn(), name)?),
-        (None, None) => print_table(Variable::get_all(b.conn())?),
+    }
+    println!();
+    print_table(vars);
+    println!();
+    Ok(())
+}
+pub fn workflows(b: &Bastion, matches: &ArgMatches) -> Result<()> {
+    println!();
+    match matches.value_of("name") {
+        Some(name) => print_table(WorkflowStep::get_by_name(b.conn(), name)?),
+        None => print_table(WorkflowStep::get_all(b.conn())?),
     };
     println!();
     Ok(())
 }
+pub fn config(b: &Bastion, matches: &ArgMatches) -> Result<()> {
+    let environment = matches.value_of("environment");
+    let request = matches.value_of("request");
+    println!();
+    print_table(Config::get_by(b.conn(), |x| {
+        (environment.is_none() || x.environment() == environment)
+            && (request.is_none() || x.request_name() == request)
+    })?);
+    println!();
+    Ok(())
+}
 pub fn options(b: &Bastion, _matches: &ArgMatches) -> Result<()> {
     if b.current_request().is_none() {
         return Err(Error::new(ErrorKind::RequestStateExpected("Show options")));
@@ -42,6 +71,15 @@ pub fn options(b: &Bastion, _matches: &ArgMatches) -> Result<()> {
     println!();
     Ok(())
 }
+pub fn oauth2(b: &Bastion, matches: &ArgMatches) -> Result<()> {
+    println!();
+    match matches.value_of("environment") {
+        Some(env) => print_table(OAuth2::get_by_name(b.conn(), env)?),
+        None => print_table(OAuth2::get_all(b.conn())?),
+    };
+    println!();
+    Ok(())
+}
 pub fn environments(b: &Bastion, _matches: &ArgMatches) -> Result<()> {
     println!();
     print_table(Environment::get_all(b.conn())?);
@@ -51,14 +89,37 @@ pub fn environments(b: &Bastion, _matches: &ArgMatches) -> Result<()> {
 pub fn response(b: &Bastion, matches: &ArgMatches) -> Result<()> {
     let id = matches.value_of("id");
     if id.is_none() {
+        let limit = match matches.value_of("limit") {
+            Some(x) => Some(x.parse()?),
+            None => None,
+        };
         println!();
-        print_table(RequestResponse::get_all(b.conn())?);
+        print_table(RequestResponse::get_history(
+            b.conn(),
+            matches.value_of("request"),
+            matches.value_of("environment"),
+            matches.value_of("status"),
+            limit,
+        )?);
         println!();
         return Ok(());
     }
     let id = id.unwrap();
     let rr = RequestResponse::get_by_id(b.conn(), id.parse()?)?;
 
+    if matches.is_present("curl") {
+        println!(
+            "{}",
+            super::export::curl_command(
+                &rr.method(),
+                rr.url(),
+                rr.request_headers(),
+                rr.request_body().map(|x| x.as_ref())
+            )
+        );
+        return Ok(());
+    }
+
     let tx = matches.is_present("transmitted");
     let rx = matches.is_present("received");
     let (tx, rx) = match (tx, rx) {
@@ -78,6 +139,16 @@ pub fn response(b: &Bastion, matches: &ArgMatches) -> Result<()> {
         }
         println!();
 
+        let input_options = rr.input_options();
+        if !input_options.is_empty() {
+            println!("{}", "  Input Options".bold());
+            println!("  ===============");
+            for (name, value) in input_options {
+                println!("{}", format!("{} = {}", name, value).bright_black());
+            }
+            println!();
+        }
+
         if let Some(body) = rr.request_body() {
             println!("{}", "  Request Body".bold());
             println!("  ==============");
@@ -115,6 +186,32 @@ pub fn response(b: &Bastion, matches: &ArgMatches) -> Result<()> {
             }
             println!();
         }
+
+        let results = rr.assertion_results();
+        if !results.is_empty() {
+            println!("{}", "  Assertions".bold());
+            println!("  ============");
+            for (description, result) in results {
+                super::assert::print_result(description, result);
+            }
+            println!();
+        }
+
+        if rr.duration_ms().is_some() {
+            println!("{}", "  Metrics".bold());
+            println!("  =========");
+            let metrics = vec![
+                ("sent", String::from(rr.timestamp().unwrap_or("-"))),
+                ("total time", format_ms(rr.duration_ms())),
+                ("time to first byte", format_ms(rr.ttfb_ms())),
+                ("request body size", format_size(rr.request_size())),
+                ("response body size", format_size(rr.response_size())),
+            ];
+            for (name, value) in metrics {
+                println!("{}", format!("{} = {}", name, value).bright_black());
+            }
+            println!();
+        }
     }
 
     Ok(())
@@ -0,0 +1,93 @@
+use super::run::{self, RunOptions, Verbosity};
+use crate::bastion::Bastion;
+use crate::db::{Assertion, DbObject, PrintableTableStruct, Request};
+use crate::error::{Error, ErrorKind, Result};
+use clap_v3::ArgMatches;
+use colored::*;
+use comfy_table::{Cell, Color};
+
+struct TestResult {
+    request: String,
+    statuses: Vec<String>,
+    passed: usize,
+    failed: usize,
+    error: Option<String>,
+}
+
+pub fn execute(b: &mut Bastion, _matches: &ArgMatches) -> Result<()> {
+    let mut names = Assertion::collect_all(b.conn(), |x| String::from(x.request_name()))?;
+    names.sort();
+
+    let mut results = vec![];
+    for name in names {
+        println!("{}", name.bold());
+        let mut result = TestResult {
+            request: name.clone(),
+            statuses: vec![],
+            passed: 0,
+            failed: 0,
+            error: None,
+        };
+        let req = Request::get_by_name(b.conn(), &name)?.remove(0);
+        match run::run_request(b, &req, RunOptions::new(Verbosity::Silent)) {
+            Ok(rrs) => {
+                for rr in rrs {
+                    result
+                        .statuses
+                        .push(String::from(rr.status().unwrap_or("-")));
+                    for (_, x) in rr.assertion_results() {
+                        match x {
+                            "pass" => result.passed += 1,
+                            _ => result.failed += 1,
+                        }
+                    }
+                }
+            }
+            Err(x) => {
+                println!("[!] {}", x);
+                result.error = Some(format!("{}", x));
+            }
+        }
+        println!();
+        results.push(result);
+    }
+
+    let failed: usize = results
+        .iter()
+        .map(|x| x.failed + x.error.is_some() as usize)
+        .sum();
+    println!("  Summary");
+    super::show::print_table(results);
+    println!();
+
+    if failed > 0 {
+        return Err(Error::new(ErrorKind::AssertionsFailed(failed)));
+    }
+    Ok(())
+}
+
+impl PrintableTableStruct for TestResult {
+    fn get_header() -> Vec<Cell> {
+        vec![
+            Cell::new("request"),
+            Cell::new("status"),
+            Cell::new("passed"),
+            Cell::new("failed"),
+            Cell::new("result"),
+        ]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        let result = match (&self.error, self.failed) {
+            (Some(x), _) => Cell::new(x).fg(Color::Red),
+            (None, 0) => Cell::new("pass").fg(Color::Green),
+            (None, _) => Cell::new("fail").fg(Color::Red),
+        };
+        vec![vec![
+            Cell::new(&self.request),
+            Cell::new(self.statuses.join("\n")),
+            Cell::new(self.passed),
+            Cell::new(self.failed),
+            result,
+        ]]
+    }
+}
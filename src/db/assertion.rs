@@ -0,0 +1,167 @@
+use super::{DbObject, PrintableTableStruct};
+use crate::error::Result;
+use comfy_table::Cell;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+#[derive(Clone)]
+pub struct Assertion {
+    rowid: u32,
+    request_name: String,
+    assertion_type: String,
+    source: String,
+    comparison: String,
+    expected: Option<String>,
+}
+
+impl Assertion {
+    pub fn new(
+        req_name: &str,
+        typ: &str,
+        source: &str,
+        comparison: &str,
+        expected: Option<&str>,
+    ) -> Assertion {
+        Assertion {
+            rowid: 0,
+            request_name: String::from(req_name),
+            assertion_type: String::from(typ),
+            source: String::from(source),
+            comparison: String::from(comparison),
+            expected: expected.map(String::from),
+        }
+    }
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS assertions (
+                  rowid             INTEGER PRIMARY KEY,
+                  request_name      TEXT NOT NULL,
+                  assertion_type    TEXT NOT NULL,
+                  source            TEXT NOT NULL,
+                  comparison        TEXT NOT NULL,
+                  expected          TEXT,
+                  FOREIGN KEY(request_name) REFERENCES requests(name)
+              )",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+
+    pub fn id(&self) -> u32 {
+        self.rowid
+    }
+    pub fn request_name(&self) -> &str {
+        self.request_name.as_ref()
+    }
+    pub fn assertion_type(&self) -> &str {
+        self.assertion_type.as_ref()
+    }
+    pub fn source(&self) -> &str {
+        self.source.as_ref()
+    }
+    pub fn comparison(&self) -> &str {
+        self.comparison.as_ref()
+    }
+    pub fn expected(&self) -> Option<&str> {
+        self.expected.as_deref()
+    }
+
+    // A short human readable form, e.g. "header content-type matches json"
+    pub fn description(&self) -> String {
+        let mut items = vec![self.assertion_type.as_ref()];
+        if !self.source.is_empty() {
+            items.push(self.source.as_ref());
+        }
+        items.push(self.comparison.as_ref());
+        if let Some(expected) = &self.expected {
+            items.push(expected.as_ref());
+        }
+        items.join(" ")
+    }
+}
+
+impl DbObject for Assertion {
+    fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO assertions
+                (request_name, assertion_type, source, comparison, expected)
+                VALUES (?1, ?2, ?3, ?4, ?5);",
+            params![
+                self.request_name,
+                self.assertion_type,
+                self.source,
+                self.comparison,
+                self.expected,
+            ],
+        )?;
+        Ok(())
+    }
+    fn delete(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM assertions WHERE rowid = ?1;",
+            params![self.rowid],
+        )?;
+        Ok(())
+    }
+    fn update(&self, conn: &Connection) -> Result<usize> {
+        let num = conn.execute(
+            "UPDATE assertions SET
+                assertion_type = ?1, source = ?2, comparison = ?3, expected = ?4
+            WHERE rowid = ?5;",
+            params![
+                self.assertion_type,
+                self.source,
+                self.comparison,
+                self.expected,
+                self.rowid
+            ],
+        )?;
+        Ok(num)
+    }
+    fn get_all(conn: &Connection) -> Result<Vec<Assertion>> {
+        let mut stmt = conn.prepare(
+            "SELECT
+                rowid, request_name, assertion_type, source, comparison, expected
+            FROM assertions ORDER BY rowid;",
+        )?;
+
+        let assertions = stmt.query_map(NO_PARAMS, |row| {
+            Ok(Assertion {
+                rowid: row.get(0)?,
+                request_name: row.get(1)?,
+                assertion_type: row.get(2)?,
+                source: row.get(3)?,
+                comparison: row.get(4)?,
+                expected: row.get(5)?,
+            })
+        })?;
+
+        // TODO: print a warning for errors
+        Ok(assertions.filter_map(|a| a.ok()).collect())
+    }
+    fn name(&self) -> &str {
+        self.request_name()
+    }
+}
+
+impl PrintableTableStruct for Assertion {
+    fn get_header() -> Vec<Cell> {
+        vec![
+            Cell::new("id"),
+            Cell::new("request_name"),
+            Cell::new("type"),
+            Cell::new("source"),
+            Cell::new("comparison"),
+            Cell::new("expected"),
+        ]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![
+            Cell::new(self.rowid),
+            Cell::new(&self.request_name),
+            Cell::new(&self.assertion_type),
+            Cell::new(&self.source),
+            Cell::new(&self.comparison),
+            Cell::new(self.expected.as_deref().unwrap_or("")),
+        ]]
+    }
+}
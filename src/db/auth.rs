@@ -0,0 +1,136 @@
+use super::OAuth2;
+use crate::error::{Error, ErrorKind, Result};
+
+pub const MASK: &str = "****";
+
+// Authentication attached to a request. Credentials are input option
+// placeholders such as {token} until the options are replaced.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Auth {
+    Basic(String, String),
+    Bearer(String),
+    // header or query parameter name, key
+    ApiKeyHeader(String, String),
+    ApiKeyQuery(String, String),
+    // access token, fetched for the environment the request is sent from
+    OAuth2(String),
+}
+
+impl Auth {
+    // Parse the stored form, e.g. `basic {user} {password}`
+    pub fn parse(s: &str) -> Result<Auth> {
+        let tokens = shlex::split(s).unwrap_or_default();
+        let tokens: Vec<&str> = tokens.iter().map(|x| x.as_ref()).collect();
+        match tokens.as_slice() {
+            ["basic", user, password] => Ok(Auth::Basic(user.to_string(), password.to_string())),
+            ["bearer", token] => Ok(Auth::Bearer(token.to_string())),
+            ["oauth2"] => Ok(Auth::oauth2()),
+            ["apikey", "header", name, key] => {
+                Ok(Auth::ApiKeyHeader(name.to_string(), key.to_string()))
+            }
+            ["apikey", "query", name, key] => {
+                Ok(Auth::ApiKeyQuery(name.to_string(), key.to_string()))
+            }
+            _ => Err(Error::new(ErrorKind::ParseError)),
+        }
+    }
+    // The inverse of parse
+    pub fn to_auth_string(&self) -> String {
+        let tokens = match self {
+            Auth::Basic(user, password) => vec!["basic", user, password],
+            Auth::Bearer(token) => vec!["bearer", token],
+            Auth::ApiKeyHeader(name, key) => vec!["apikey", "header", name, key],
+            Auth::ApiKeyQuery(name, key) => vec!["apikey", "query", name, key],
+            Auth::OAuth2(_) => vec!["oauth2"],
+        };
+        tokens
+            .into_iter()
+            .map(|x| shlex::quote(x).into_owned())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+    // A description without credentials, e.g. "api key (header X-API-Key)"
+    pub fn summary(&self) -> String {
+        match self {
+            Auth::Basic(user, _) => format!("basic ({})", user),
+            Auth::Bearer(_) => String::from("bearer"),
+            Auth::ApiKeyHeader(name, _) => format!("api key (header {})", name),
+            Auth::ApiKeyQuery(name, _) => format!("api key (query {})", name),
+            Auth::OAuth2(_) => String::from("oauth2"),
+        }
+    }
+
+    pub fn values(&self) -> Vec<&str> {
+        match self {
+            Auth::Basic(user, password) => vec![user, password],
+            Auth::Bearer(token) | Auth::OAuth2(token) => vec![token],
+            Auth::ApiKeyHeader(_, key) | Auth::ApiKeyQuery(_, key) => vec![key],
+        }
+    }
+    // The values that must never be displayed
+    pub fn secrets(&self) -> Vec<&str> {
+        match self {
+            Auth::Basic(_, password) => vec![password],
+            Auth::Bearer(token) | Auth::OAuth2(token) => vec![token],
+            Auth::ApiKeyHeader(_, key) | Auth::ApiKeyQuery(_, key) => vec![key],
+        }
+    }
+    // The header that carries the credentials, if any
+    pub fn header(&self) -> Option<&str> {
+        match self {
+            Auth::Basic(_, _) | Auth::Bearer(_) | Auth::OAuth2(_) => Some("authorization"),
+            Auth::ApiKeyHeader(name, _) => Some(name),
+            Auth::ApiKeyQuery(_, _) => None,
+        }
+    }
+    pub fn map_values<F: FnMut(&str) -> String>(&self, mut f: F) -> Auth {
+        match self {
+            Auth::Basic(user, password) => Auth::Basic(f(user), f(password)),
+            Auth::Bearer(token) => Auth::Bearer(f(token)),
+            Auth::ApiKeyHeader(name, key) => Auth::ApiKeyHeader(name.clone(), f(key)),
+            Auth::ApiKeyQuery(name, key) => Auth::ApiKeyQuery(name.clone(), f(key)),
+            Auth::OAuth2(token) => Auth::OAuth2(f(token)),
+        }
+    }
+    // The token is read from the variable the token endpoint response is
+    // cached in
+    pub fn oauth2() -> Auth {
+        Auth::OAuth2(format!("{{{}}}", OAuth2::ACCESS_TOKEN_VARIABLE))
+    }
+    pub fn is_oauth2(&self) -> bool {
+        matches!(self, Auth::OAuth2(_))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn auth_string_round_trip() {
+        let auths = vec![
+            Auth::Basic(String::from("{user}"), String::from("{password}")),
+            Auth::Bearer(String::from("{token}")),
+            Auth::ApiKeyHeader(String::from("X-API-Key"), String::from("{key}")),
+            Auth::ApiKeyQuery(String::from("api_key"), String::from("{key}")),
+            Auth::oauth2(),
+            // values with spaces and quotes are quoted
+            Auth::Basic(String::from("me"), String::from("pass word'\"")),
+        ];
+        for auth in auths {
+            assert_eq!(Auth::parse(&auth.to_auth_string()).unwrap(), auth);
+        }
+    }
+
+    #[test]
+    fn parse_auth_string() {
+        assert_eq!(
+            Auth::parse("bearer {token}").unwrap(),
+            Auth::Bearer(String::from("{token}"))
+        );
+        assert_eq!(Auth::parse("oauth2").unwrap(), Auth::oauth2());
+        assert!(Auth::parse("bearer").is_err());
+        assert!(Auth::parse("apikey cookie name {key}").is_err());
+        assert!(Auth::parse("digest {user} {password}").is_err());
+    }
+}
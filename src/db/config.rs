@@ -0,0 +1,166 @@
+use super::auth::MASK;
+use super::secret;
+use super::{DbObject, PrintableTableStruct};
+use crate::error::Result;
+use comfy_table::Cell;
+use rusqlite::{params, Connection, NO_PARAMS};
+use std::collections::BTreeMap;
+
+// A HTTP client setting for the whole workspace, or an override for a single
+// environment or request.
+pub struct Config {
+    key: String,
+    value: String,
+    environment: Option<String>,
+    request_name: Option<String>,
+}
+
+impl Config {
+    pub const TIMEOUT: &'static str = "timeout";
+    pub const CONNECT_TIMEOUT: &'static str = "connect-timeout";
+    pub const FOLLOW_REDIRECTS: &'static str = "follow-redirects";
+    pub const MAX_REDIRECTS: &'static str = "max-redirects";
+    pub const PROXY: &'static str = "proxy";
+    pub const USER_AGENT: &'static str = "user-agent";
+    pub const GZIP: &'static str = "gzip";
+    pub const HTTP_VERSION: &'static str = "http-version";
+    pub const CA_CERT: &'static str = "ca-cert";
+    pub const CLIENT_CERT: &'static str = "client-cert";
+    pub const CLIENT_KEY: &'static str = "client-key";
+    pub const CLIENT_CERT_PASSWORD: &'static str = "client-cert-password";
+    pub const INSECURE: &'static str = "insecure";
+
+    pub fn new(
+        key: &str,
+        value: &str,
+        environment: Option<&str>,
+        request_name: Option<&str>,
+    ) -> Config {
+        Config {
+            key: String::from(key),
+            value: String::from(value),
+            environment: environment.map(String::from),
+            request_name: request_name.map(String::from),
+        }
+    }
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS config (
+                  key               TEXT NOT NULL,
+                  value             TEXT NOT NULL,
+                  environment       TEXT,
+                  request_name      TEXT
+              )",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_ref()
+    }
+    pub fn value(&self) -> &str {
+        self.value.as_ref()
+    }
+    pub fn environment(&self) -> Option<&str> {
+        self.environment.as_deref()
+    }
+    pub fn request_name(&self) -> Option<&str> {
+        self.request_name.as_deref()
+    }
+    pub fn scope(&self) -> String {
+        match (&self.environment, &self.request_name) {
+            (Some(env), _) => format!("environment {}", env),
+            (_, Some(req)) => format!("request {}", req),
+            (None, None) => String::from("workspace"),
+        }
+    }
+
+    // The settings in effect for a request sent from an environment. Request
+    // overrides take precedence over environment overrides, which take
+    // precedence over workspace settings.
+    pub fn resolve(
+        conn: &Connection,
+        environment: Option<&str>,
+        request_name: Option<&str>,
+    ) -> Result<BTreeMap<String, String>> {
+        let mut settings = BTreeMap::new();
+        let mut configs = vec![vec![], vec![], vec![]];
+        for config in Config::get_all(conn)? {
+            match (config.environment(), config.request_name()) {
+                (None, None) => configs[0].push(config),
+                (Some(env), None) if Some(env) == environment => configs[1].push(config),
+                (None, Some(req)) if Some(req) == request_name => configs[2].push(config),
+                _ => (),
+            }
+        }
+        for config in configs.into_iter().flatten() {
+            settings.insert(config.key, config.value);
+        }
+        Ok(settings)
+    }
+}
+
+impl DbObject for Config {
+    fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO config (key, value, environment, request_name)
+                VALUES (?1, ?2, ?3, ?4);",
+            params![self.key, self.value, self.environment, self.request_name],
+        )?;
+        Ok(())
+    }
+    fn delete(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM config
+                WHERE key = ?1 AND environment IS ?2 AND request_name IS ?3;",
+            params![self.key, self.environment, self.request_name],
+        )?;
+        Ok(())
+    }
+    fn update(&self, conn: &Connection) -> Result<usize> {
+        let num = conn.execute(
+            "UPDATE config SET value = ?1
+                WHERE key = ?2 AND environment IS ?3 AND request_name IS ?4;",
+            params![self.value, self.key, self.environment, self.request_name],
+        )?;
+        Ok(num)
+    }
+    fn get_all(conn: &Connection) -> Result<Vec<Config>> {
+        let mut stmt = conn.prepare(
+            "SELECT key, value, environment, request_name FROM config
+                ORDER BY environment, request_name, key;",
+        )?;
+
+        let configs = stmt.query_map(NO_PARAMS, |row| {
+            Ok(Config {
+                key: row.get(0)?,
+                value: row.get(1)?,
+                environment: row.get(2)?,
+                request_name: row.get(3)?,
+            })
+        })?;
+
+        // TODO: print a warning for errors
+        Ok(configs.filter_map(|x| x.ok()).collect())
+    }
+    fn name(&self) -> &str {
+        self.key()
+    }
+}
+
+impl PrintableTableStruct for Config {
+    fn get_header() -> Vec<Cell> {
+        vec![Cell::new("key"), Cell::new("value"), Cell::new("scope")]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![
+            Cell::new(&self.key),
+            Cell::new(match self.key.as_str() {
+                Config::CLIENT_CERT_PASSWORD => MASK,
+                _ => secret::mask(&self.value),
+            }),
+            Cell::new(self.scope()),
+        ]]
+    }
+}
@@ -1,4 +1,7 @@
-use super::{InputOption, OutputOption, Request, RequestResponse, Variable};
+use super::{
+    Assertion, Config, InputOption, OAuth2, OutputOption, Request, RequestResponse, Setting,
+    Variable, WorkflowStep,
+};
 use crate::error::Result;
 use comfy_table::Cell;
 use rusqlite::{Connection, NO_PARAMS};
@@ -59,13 +62,36 @@ impl Db {
         Variable::create_table(&self.conn)?;
         InputOption::create_table(&self.conn)?;
         OutputOption::create_table(&self.conn)?;
+        Assertion::create_table(&self.conn)?;
         RequestResponse::create_table(&self.conn)?;
+        Setting::create_table(&self.conn)?;
+        WorkflowStep::create_table(&self.conn)?;
+        Config::create_table(&self.conn)?;
+        OAuth2::create_table(&self.conn)?;
         self.conn.execute("PRAGMA foreign_keys = ON", NO_PARAMS)?;
 
         Ok(())
     }
 }
 
+pub fn add_missing_columns(conn: &Connection, table: &str, columns: &[(&str, &str)]) -> Result<()> {
+    let mut stmt = conn.prepare(&format!("PRAGMA table_info({});", table))?;
+    let existing: Vec<String> = stmt
+        .query_map(NO_PARAMS, |row| row.get(1))?
+        .filter_map(|x| x.ok())
+        .collect();
+    for (name, typ) in columns {
+        if existing.iter().any(|x| x == name) {
+            continue;
+        }
+        conn.execute(
+            &format!("ALTER TABLE {} ADD COLUMN {} {};", table, name, typ),
+            NO_PARAMS,
+        )?;
+    }
+    Ok(())
+}
+
 pub trait PrintableTableStruct {
     fn get_header() -> Vec<Cell>;
     fn get_rows(&self) -> Vec<Vec<Cell>>;
@@ -93,6 +119,7 @@ impl PrintableTable for (String, Vec<String>) {
     }
 }
 
+#[allow(dead_code)]
 pub trait DbObject {
     fn create(&self, conn: &Connection) -> Result<()>;
     fn delete(&self, conn: &Connection) -> Result<()>;
@@ -1,16 +1,32 @@
+pub mod assertion;
+pub mod auth;
+pub mod config;
 pub mod db;
 pub mod environment;
+pub mod oauth2;
 pub mod option;
+pub mod placeholder;
 pub mod request;
 pub mod request_response;
+pub mod secret;
+pub mod setting;
 pub mod variable;
+pub mod workflow;
+pub mod workspace_file;
 
+pub use assertion::Assertion;
+pub use auth::Auth;
+pub use config::Config;
 pub use db::Db;
 pub use db::DbObject;
 pub use db::{PrintableTable, PrintableTableStruct};
 pub use environment::Environment;
+pub use oauth2::OAuth2;
 pub use option::InputOption;
 pub use option::OutputOption;
 pub use request::Request;
 pub use request_response::RequestResponse;
+pub use setting::Setting;
 pub use variable::Variable;
+pub use workflow::WorkflowStep;
+pub use workspace_file::WorkspaceFile;
@@ -0,0 +1,216 @@
+use super::{DbObject, PrintableTableStruct};
+use crate::error::Result;
+use comfy_table::Cell;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+// How an environment obtains OAuth2 access tokens. Credentials are stored as
+// variable names so the secrets themselves stay in the variables table.
+pub struct OAuth2 {
+    environment: String,
+    token_url: String,
+    grant_type: String,
+    client_id: String,
+    client_secret: Option<String>,
+    scopes: Option<String>,
+    username: Option<String>,
+    password: Option<String>,
+    refresh_token: Option<String>,
+}
+
+impl OAuth2 {
+    pub const CLIENT_CREDENTIALS: &'static str = "client_credentials";
+    pub const PASSWORD: &'static str = "password";
+    pub const REFRESH_TOKEN: &'static str = "refresh_token";
+
+    // Variables that cache the tokens returned by the token endpoint
+    pub const ACCESS_TOKEN_VARIABLE: &'static str = "oauth2.access_token";
+    pub const REFRESH_TOKEN_VARIABLE: &'static str = "oauth2.refresh_token";
+    pub const SOURCE: &'static str = "oauth2";
+
+    pub fn new(environment: &str, token_url: &str, grant_type: &str, client_id: &str) -> OAuth2 {
+        OAuth2 {
+            environment: String::from(environment),
+            token_url: String::from(token_url),
+            grant_type: String::from(grant_type),
+            client_id: String::from(client_id),
+            client_secret: None,
+            scopes: None,
+            username: None,
+            password: None,
+            refresh_token: None,
+        }
+    }
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS oauth2 (
+                  environment       TEXT NOT NULL UNIQUE,
+                  token_url         TEXT NOT NULL,
+                  grant_type        TEXT NOT NULL,
+                  client_id         TEXT NOT NULL,
+                  client_secret     TEXT,
+                  scopes            TEXT,
+                  username          TEXT,
+                  password          TEXT,
+                  refresh_token     TEXT
+              )",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+
+    pub fn environment(&self) -> &str {
+        self.environment.as_ref()
+    }
+    pub fn token_url(&self) -> &str {
+        self.token_url.as_ref()
+    }
+    pub fn grant_type(&self) -> &str {
+        self.grant_type.as_ref()
+    }
+    pub fn client_id(&self) -> &str {
+        self.client_id.as_ref()
+    }
+    pub fn client_secret(&self) -> Option<&str> {
+        self.client_secret.as_deref()
+    }
+    pub fn scopes(&self) -> Option<&str> {
+        self.scopes.as_deref()
+    }
+    pub fn username(&self) -> Option<&str> {
+        self.username.as_deref()
+    }
+    pub fn password(&self) -> Option<&str> {
+        self.password.as_deref()
+    }
+    pub fn refresh_token(&self) -> Option<&str> {
+        self.refresh_token.as_deref()
+    }
+    pub fn set_client_secret(&mut self, client_secret: Option<&str>) {
+        self.client_secret = client_secret.map(String::from);
+    }
+    pub fn set_scopes(&mut self, scopes: Option<&str>) {
+        self.scopes = scopes.map(String::from);
+    }
+    pub fn set_username(&mut self, username: Option<&str>) {
+        self.username = username.map(String::from);
+    }
+    pub fn set_password(&mut self, password: Option<&str>) {
+        self.password = password.map(String::from);
+    }
+    pub fn set_refresh_token(&mut self, refresh_token: Option<&str>) {
+        self.refresh_token = refresh_token.map(String::from);
+    }
+}
+
+impl DbObject for OAuth2 {
+    fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO oauth2 (environment, token_url, grant_type, client_id,
+                client_secret, scopes, username, password, refresh_token)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9);",
+            params![
+                self.environment,
+                self.token_url,
+                self.grant_type,
+                self.client_id,
+                self.client_secret,
+                self.scopes,
+                self.username,
+                self.password,
+                self.refresh_token,
+            ],
+        )?;
+        Ok(())
+    }
+    fn delete(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM oauth2 WHERE environment = ?1;",
+            params![self.environment],
+        )?;
+        Ok(())
+    }
+    fn update(&self, conn: &Connection) -> Result<usize> {
+        let num = conn.execute(
+            "UPDATE oauth2 SET
+                token_url = ?2,
+                grant_type = ?3,
+                client_id = ?4,
+                client_secret = ?5,
+                scopes = ?6,
+                username = ?7,
+                password = ?8,
+                refresh_token = ?9
+            WHERE environment = ?1;",
+            params![
+                self.environment,
+                self.token_url,
+                self.grant_type,
+                self.client_id,
+                self.client_secret,
+                self.scopes,
+                self.username,
+                self.password,
+                self.refresh_token,
+            ],
+        )?;
+        Ok(num)
+    }
+    fn get_all(conn: &Connection) -> Result<Vec<OAuth2>> {
+        let mut stmt = conn.prepare(
+            "SELECT environment, token_url, grant_type, client_id, client_secret,
+                scopes, username, password, refresh_token
+                FROM oauth2 ORDER BY environment;",
+        )?;
+
+        let configs = stmt.query_map(NO_PARAMS, |row| {
+            Ok(OAuth2 {
+                environment: row.get(0)?,
+                token_url: row.get(1)?,
+                grant_type: row.get(2)?,
+                client_id: row.get(3)?,
+                client_secret: row.get(4)?,
+                scopes: row.get(5)?,
+                username: row.get(6)?,
+                password: row.get(7)?,
+                refresh_token: row.get(8)?,
+            })
+        })?;
+
+        // TODO: print a warning for errors
+        Ok(configs.filter_map(|x| x.ok()).collect())
+    }
+    fn name(&self) -> &str {
+        self.environment()
+    }
+}
+
+impl PrintableTableStruct for OAuth2 {
+    fn get_header() -> Vec<Cell> {
+        vec![
+            Cell::new("environment"),
+            Cell::new("token url"),
+            Cell::new("grant type"),
+            Cell::new("variables"),
+            Cell::new("scopes"),
+        ]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        let variables: Vec<&str> = vec![
+            Some(self.client_id()),
+            self.client_secret(),
+            self.username(),
+            self.password(),
+            self.refresh_token(),
+        ]
+        .into_iter()
+        .flatten()
+        .collect();
+        vec![vec![
+            Cell::new(&self.environment),
+            Cell::new(&self.token_url),
+            Cell::new(&self.grant_type),
+            Cell::new(variables.join("\n")),
+            Cell::new(self.scopes().unwrap_or("")),
+        ]]
+    }
+}
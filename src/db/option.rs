@@ -1,5 +1,7 @@
-use super::DbObject;
+use super::auth::MASK;
+use super::secret;
 use super::PrintableTableStruct;
+use super::{DbObject, OAuth2};
 use crate::error::Result;
 use comfy_table::Cell;
 use rusqlite::{params, Connection, NO_PARAMS};
@@ -9,16 +11,27 @@ pub struct InputOption {
     request_name: String,
     option_name: String,
     values: Vec<String>,
+    // The value used when the option has no values, from {name:default}
+    default: Option<String>,
+    // Optional options, {name?} or {?name}, are left out when they have no
+    // value
+    optional: bool,
 }
 
 impl InputOption {
     const VALUE_SEPARATOR: &'static str = "\n~\n";
+    // Matches {name}, {name:default}, {name?} and {?name}. The first group
+    // is the leading '?', the second the name and the third ':default' or
+    // the trailing '?'.
+    pub const PATTERN: &'static str = r"\{(\?)?([\w.-]+)(\?|:[^{}]*)?\}";
 
     pub fn new(req_name: &str, opt_name: &str, values: Vec<String>) -> InputOption {
         InputOption {
             request_name: String::from(req_name),
             option_name: String::from(opt_name),
             values,
+            default: None,
+            optional: false,
         }
     }
     pub fn create_table(conn: &Connection) -> Result<()> {
@@ -27,11 +40,21 @@ impl InputOption {
                   request_name    TEXT NOT NULL,
                   option_name     TEXT NOT NULL,
                   value           TEXT,
+                  default_value   TEXT,
+                  optional        INTEGER NOT NULL DEFAULT 0,
                   FOREIGN KEY(request_name) REFERENCES requests(name),
                   UNIQUE(request_name, option_name)
               )",
             NO_PARAMS,
         )?;
+        super::db::add_missing_columns(
+            conn,
+            "input_options",
+            &[
+                ("default_value", "TEXT"),
+                ("optional", "INTEGER NOT NULL DEFAULT 0"),
+            ],
+        )?;
         Ok(())
     }
 
@@ -53,6 +76,35 @@ impl InputOption {
     pub fn set_values(&mut self, values: Vec<&str>) {
         self.values = values.into_iter().map(String::from).collect();
     }
+    pub fn default(&self) -> Option<&str> {
+        self.default.as_deref()
+    }
+    pub fn set_default(&mut self, default: Option<&str>) {
+        self.default = default.map(String::from);
+    }
+    pub fn set_optional(&mut self, optional: bool) {
+        self.optional = optional;
+    }
+    // The values to send: the current values, or else the default
+    pub fn effective_values(&self) -> Vec<&str> {
+        match (self.values.len(), self.default()) {
+            (0, Some(default)) => vec![default],
+            _ => self.values(),
+        }
+    }
+    // A required option without values or a default
+    pub fn is_missing(&self) -> bool {
+        !self.optional && self.effective_values().is_empty()
+    }
+
+    // The default for display, noting options that may be left out
+    pub fn default_summary(&self) -> String {
+        match (self.default(), self.optional) {
+            (Some(default), _) => String::from(default),
+            (None, true) => String::from("(optional)"),
+            (None, false) => String::new(),
+        }
+    }
 
     fn stringify_values(values: Vec<&str>) -> Option<String> {
         match values.len() {
@@ -74,12 +126,15 @@ impl InputOption {
 impl DbObject for InputOption {
     fn create(&self, conn: &Connection) -> Result<()> {
         conn.execute(
-            "INSERT INTO input_options (request_name, option_name, value)
-                  VALUES (?1, ?2, ?3);",
+            "INSERT INTO input_options
+                (request_name, option_name, value, default_value, optional)
+                VALUES (?1, ?2, ?3, ?4, ?5);",
             params![
                 self.request_name,
                 self.option_name,
-                InputOption::stringify_values(self.values())
+                InputOption::stringify_values(self.values()),
+                self.default,
+                self.optional
             ],
         )?;
         Ok(())
@@ -95,10 +150,12 @@ impl DbObject for InputOption {
     fn update(&self, conn: &Connection) -> Result<usize> {
         let num = conn.execute(
             "UPDATE input_options SET
-                value = ?1
-            WHERE request_name = ?2 AND option_name = ?3;",
+                value = ?1, default_value = ?2, optional = ?3
+            WHERE request_name = ?4 AND option_name = ?5;",
             params![
                 InputOption::stringify_values(self.values()),
+                self.default,
+                self.optional,
                 self.request_name,
                 self.option_name
             ],
@@ -106,14 +163,18 @@ impl DbObject for InputOption {
         Ok(num)
     }
     fn get_all(conn: &Connection) -> Result<Vec<InputOption>> {
-        let mut stmt =
-            conn.prepare("SELECT request_name, option_name, value FROM input_options;")?;
+        let mut stmt = conn.prepare(
+            "SELECT request_name, option_name, value, default_value, optional
+                FROM input_options;",
+        )?;
 
         let opts = stmt.query_map(NO_PARAMS, |row| {
             Ok(InputOption {
                 request_name: row.get(0)?,
                 option_name: row.get(1)?,
                 values: InputOption::unstringify_values(row.get(2)?),
+                default: row.get(3)?,
+                optional: row.get(4)?,
             })
         })?;
 
@@ -127,12 +188,26 @@ impl DbObject for InputOption {
 
 impl PrintableTableStruct for InputOption {
     fn get_header() -> Vec<Cell> {
-        vec![Cell::new("option_name"), Cell::new("values")]
+        vec![
+            Cell::new("option_name"),
+            Cell::new("values"),
+            Cell::new("default"),
+        ]
     }
     fn get_rows(&self) -> Vec<Vec<Cell>> {
         vec![vec![
             Cell::new(&self.option_name),
-            Cell::new(self.values().join("\n")),
+            Cell::new(
+                self.values()
+                    .into_iter()
+                    .map(|x| match self.option_name.as_str() {
+                        OAuth2::ACCESS_TOKEN_VARIABLE => MASK,
+                        _ => secret::mask(x),
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+            Cell::new(self.default_summary()),
         ]]
     }
 }
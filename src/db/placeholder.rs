@@ -0,0 +1,157 @@
+use crate::error::{Error, ErrorKind, Result};
+use chrono::{SecondsFormat, Utc};
+use rand::distributions::Alphanumeric;
+use rand::Rng;
+use regex::{Captures, Regex};
+use std::collections::HashMap;
+
+// Built-in placeholders such as {$uuid} or {$randint:1:100}. They are not
+// input options and are expanded when a request is sent.
+pub struct Placeholders {
+    re: Regex,
+    // every occurrence of a placeholder in one request has the same value
+    values: HashMap<String, String>,
+}
+
+impl Placeholders {
+    pub fn new() -> Placeholders {
+        Placeholders {
+            re: Regex::new(r"\{\$([a-z0-9]+)(?::([^{}]*))?\}").unwrap(),
+            values: HashMap::new(),
+        }
+    }
+
+    pub fn expand(&mut self, s: &str) -> Result<String> {
+        let mut error = None;
+        let values = &mut self.values;
+        let expanded = self.re.replace_all(s, |cap: &Captures| {
+            let placeholder = cap.get(0).unwrap().as_str();
+            if let Some(value) = values.get(placeholder) {
+                return value.clone();
+            }
+            match evaluate(&cap[1], cap.get(2).map(|x| x.as_str())) {
+                Some(value) => {
+                    values.insert(String::from(placeholder), value.clone());
+                    value
+                }
+                None => {
+                    error = error.take().or_else(|| Some(String::from(placeholder)));
+                    String::from(placeholder)
+                }
+            }
+        });
+        match error {
+            Some(x) => Err(Error::new(ErrorKind::InvalidPlaceholder(x))),
+            None => Ok(expanded.into_owned()),
+        }
+    }
+}
+
+// longest string {$randstr:N} may generate
+const MAX_RANDSTR_LEN: usize = 4096;
+
+fn evaluate(function: &str, args: Option<&str>) -> Option<String> {
+    let mut rng = rand::thread_rng();
+    match (function, args) {
+        ("uuid", None) => Some(uuid::Uuid::new_v4().to_string()),
+        ("timestamp", None) => Some(Utc::now().timestamp().to_string()),
+        ("isotime", None) => Some(Utc::now().to_rfc3339_opts(SecondsFormat::Secs, true)),
+        ("randint", Some(args)) => {
+            let mut bounds = args.splitn(2, ':').map(|x| x.trim().parse::<i64>());
+            match (bounds.next(), bounds.next()) {
+                (Some(Ok(low)), Some(Ok(high))) if low <= high => {
+                    Some(rng.gen_range(low..=high).to_string())
+                }
+                _ => None,
+            }
+        }
+        ("randstr", Some(len)) => {
+            let len: usize = len.trim().parse().ok()?;
+            if len > MAX_RANDSTR_LEN {
+                return None;
+            }
+            Some((0..len).map(|_| rng.sample(Alphanumeric) as char).collect())
+        }
+        ("base64", Some(data)) => Some(base64::encode(data)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand(s: &str) -> Result<String> {
+        Placeholders::new().expand(s)
+    }
+
+    #[test]
+    fn uuid() {
+        let value = expand("{$uuid}").unwrap();
+        assert!(uuid::Uuid::parse_str(&value).is_ok());
+        assert_ne!(value, expand("{$uuid}").unwrap());
+    }
+
+    #[test]
+    fn timestamps() {
+        let before = Utc::now().timestamp();
+        let value: i64 = expand("{$timestamp}").unwrap().parse().unwrap();
+        assert!(value >= before && value <= Utc::now().timestamp());
+        let value = expand("{$isotime}").unwrap();
+        assert!(chrono::DateTime::parse_from_rfc3339(&value).is_ok());
+    }
+
+    #[test]
+    fn randint() {
+        for _ in 0..100 {
+            let value: i64 = expand("{$randint:-2:2}").unwrap().parse().unwrap();
+            assert!((-2..=2).contains(&value));
+        }
+        assert_eq!(expand("{$randint:7:7}").unwrap(), "7");
+    }
+
+    #[test]
+    fn randstr() {
+        let value = expand("{$randstr:16}").unwrap();
+        assert_eq!(value.len(), 16);
+        assert!(value.chars().all(|c| c.is_ascii_alphanumeric()));
+        assert_eq!(expand("{$randstr:0}").unwrap(), "");
+        assert_eq!(expand("{$randstr:4096}").unwrap().len(), MAX_RANDSTR_LEN);
+        assert!(expand("{$randstr:4097}").is_err());
+    }
+
+    #[test]
+    fn base64() {
+        assert_eq!(expand("{$base64:user:pass}").unwrap(), "dXNlcjpwYXNz");
+        assert_eq!(expand("{$base64:}").unwrap(), "");
+        // braces end the placeholder, so {$base64:{x}} is not one
+        assert_eq!(expand("{$base64:{x}}").unwrap(), "{$base64:{x}}");
+    }
+
+    #[test]
+    fn malformed_arguments() {
+        for s in &[
+            "{$uuid:4}",
+            "{$timestamp:now}",
+            "{$randint}",
+            "{$randint:1}",
+            "{$randint:2:1}",
+            "{$randint:a:b}",
+            "{$randstr}",
+            "{$randstr:-1}",
+            "{$base64}",
+            "{$unknown}",
+        ] {
+            let err = expand(&format!("a {} b", s)).unwrap_err();
+            assert_eq!(err.to_string(), format!("Invalid placeholder: {}", s));
+        }
+    }
+
+    #[test]
+    fn same_value_within_a_request() {
+        let mut placeholders = Placeholders::new();
+        let url = placeholders.expand("/{$uuid}/{$randstr:8}").unwrap();
+        let body = placeholders.expand("{$uuid}/{$randstr:8}").unwrap();
+        assert_eq!(url, format!("/{}", body));
+    }
+}
@@ -1,12 +1,13 @@
+use super::placeholder::Placeholders;
+use super::secret::{self, SecretKey};
 use super::PrintableTableStruct;
-use super::{DbObject, InputOption, OutputOption};
+use super::{Assertion, Auth, Config, DbObject, InputOption, OutputOption};
 use crate::error::{Error, ErrorKind, Result};
 use comfy_table::{Cell, Color};
 use regex::Regex;
 use reqwest::Method;
 use rusqlite::{params, Connection, NO_PARAMS};
-use std::collections::HashSet;
-use std::iter::FromIterator;
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 #[derive(Debug, Clone)]
 pub struct Request {
@@ -15,6 +16,8 @@ pub struct Request {
     url: String,
     headers: Option<String>,
     body: Option<Vec<u8>>,
+    combination: Option<String>,
+    auth: Option<Auth>,
 
     input_options: Vec<InputOption>,
     output_options: Vec<OutputOption>,
@@ -29,15 +32,13 @@ impl Request {
             url: String::from(url),
             headers: None,
             body: None,
+            combination: None,
+            auth: None,
 
             input_options: vec![],
             output_options: vec![],
         };
-        r.input_options = r
-            .variable_names()
-            .iter()
-            .map(|var_name| InputOption::new(r.name(), var_name, vec![]))
-            .collect();
+        r.update_input_options();
         r
     }
     pub fn create_table(conn: &Connection) -> Result<()> {
@@ -47,10 +48,17 @@ impl Request {
                   method          TEXT NOT NULL,
                   url             TEXT NOT NULL,
                   headers         TEXT,
-                  body            BLOB
+                  body            BLOB,
+                  combination     TEXT,
+                  auth            TEXT
               )",
             NO_PARAMS,
         )?;
+        super::db::add_missing_columns(
+            conn,
+            "requests",
+            &[("combination", "TEXT"), ("auth", "TEXT")],
+        )?;
         Ok(())
     }
     pub fn add_header(&mut self, key: &str, value: &str) {
@@ -62,6 +70,7 @@ impl Request {
         };
         headers.push_str(format!("{}: {}", key, value).as_ref());
         self.headers = Some(headers);
+        self.update_input_options();
     }
     pub fn add_query_param(&mut self, query: &str) {
         if self.url.contains('?') {
@@ -70,9 +79,115 @@ impl Request {
             self.url.push('?');
         }
         self.url.push_str(query);
+        self.update_input_options();
     }
     pub fn set_body(&mut self, body: Option<Vec<u8>>) {
         self.body = body;
+        self.update_input_options();
+    }
+    pub fn set_url(&mut self, url: &str) {
+        self.url = String::from(url);
+        self.update_input_options();
+    }
+    pub fn set_method(&mut self, method: Method) {
+        self.method = method;
+    }
+    // How the values of multiple input options are combined into requests:
+    // zip (the default), product, or first
+    pub fn set_combination(&mut self, combination: Option<&str>) {
+        self.combination = combination.map(String::from);
+    }
+    pub fn set_auth(&mut self, auth: Option<Auth>) {
+        self.auth = auth;
+        self.update_input_options();
+    }
+    pub fn remove_header(&mut self, key: &str) {
+        let headers: Vec<&str> = match &self.headers {
+            Some(x) => x
+                .split('\n')
+                .filter(|h| {
+                    let name = h.split(':').next().unwrap_or("");
+                    !name.trim().eq_ignore_ascii_case(key)
+                })
+                .collect(),
+            None => vec![],
+        };
+        self.headers = match headers.len() {
+            0 => None,
+            _ => Some(headers.join("\n")),
+        };
+        self.update_input_options();
+    }
+    fn update_input_options(&mut self) {
+        // keep input options in sync with the variables used in the request
+        let specs = self.option_specs();
+        self.input_options
+            .retain(|x| specs.contains_key(x.option_name()));
+        for (var_name, (default, optional)) in specs {
+            let pos = self
+                .input_options
+                .iter()
+                .position(|x| x.option_name() == var_name);
+            let opt = match pos {
+                Some(i) => &mut self.input_options[i],
+                None => {
+                    self.input_options
+                        .push(InputOption::new(&self.name, &var_name, vec![]));
+                    self.input_options.last_mut().unwrap()
+                }
+            };
+            opt.set_default(default.as_deref());
+            opt.set_optional(optional);
+        }
+    }
+    // The input options used in the request with their default and whether
+    // they are optional. When an option is used more than once, the first
+    // default is used and any optional use makes it optional.
+    fn option_specs(&self) -> BTreeMap<String, (Option<String>, bool)> {
+        // TODO: lazy static
+        let re = Regex::new(InputOption::PATTERN).unwrap();
+        let mut texts = vec![self.url.clone()];
+        texts.extend(self.headers.clone());
+        if let Some(auth) = &self.auth {
+            texts.extend(auth.values().into_iter().map(String::from));
+        }
+        if let Some(body) = &self.body {
+            texts.push(String::from_utf8_lossy(body).into_owned());
+        }
+
+        let mut specs: BTreeMap<String, (Option<String>, bool)> = BTreeMap::new();
+        for text in texts.iter() {
+            for cap in re.captures_iter(text) {
+                let modifier = cap.get(3).map(|x| x.as_str());
+                let spec = specs.entry(String::from(&cap[2])).or_default();
+                if spec.0.is_none() {
+                    spec.0 = modifier.and_then(|x| x.strip_prefix(':')).map(String::from);
+                }
+                spec.1 |= cap.get(1).is_some() || modifier == Some("?");
+            }
+        }
+        specs
+    }
+
+    pub fn name_from_url(method: &Method, url: &str) -> String {
+        // use the last path segment that is not an input option
+        let path = url.splitn(2, "://").last().unwrap();
+        let path = path.split(['?', '#']).next().unwrap();
+        let segment = path
+            .split('/')
+            .skip(1)
+            .filter(|x| !x.is_empty() && !x.starts_with('{'))
+            .last()
+            .unwrap_or("root");
+        let segment: String = segment
+            .chars()
+            .map(|c| if c.is_alphanumeric() { c } else { '-' })
+            .collect();
+        format!(
+            "{}-{}",
+            method.as_str().to_lowercase(),
+            segment.to_lowercase()
+        )
     }
 
     fn name_to_method(name: &str) -> Method {
@@ -87,6 +202,10 @@ impl Request {
             Method::PATCH
         } else if name.starts_with("head") {
             Method::HEAD
+        } else if name.starts_with("options-") {
+            Method::OPTIONS
+        } else if name.starts_with("purge-") {
+            Method::from_bytes(b"PURGE").unwrap()
         } else {
             Method::GET
         }
@@ -107,42 +226,77 @@ impl Request {
     pub fn body(&self) -> &Option<Vec<u8>> {
         &self.body
     }
+    pub fn combination(&self) -> &str {
+        self.combination.as_deref().unwrap_or("zip")
+    }
+    pub fn auth(&self) -> Option<&Auth> {
+        self.auth.as_ref()
+    }
+    // Input options holding credentials or secret variables, which are
+    // masked when displayed
+    pub fn secret_option_names(&self) -> HashSet<String> {
+        let re = Regex::new(InputOption::PATTERN).unwrap();
+        let mut names: HashSet<String> = self
+            .input_options
+            .iter()
+            .filter(|x| x.values().iter().any(|v| secret::is_encrypted(v)))
+            .map(|x| String::from(x.option_name()))
+            .collect();
+        if let Some(auth) = &self.auth {
+            for secret in auth.secrets() {
+                for cap in re.captures_iter(secret) {
+                    names.insert(String::from(&cap[2]));
+                }
+            }
+        }
+        names
+    }
     pub fn input_options(&self) -> &Vec<InputOption> {
         &self.input_options
     }
+    pub fn has_encrypted_options(&self) -> bool {
+        self.input_options
+            .iter()
+            .any(|x| x.values().iter().any(|v| secret::is_encrypted(v)))
+    }
+    // Replace encrypted option values with their plaintext. Returns the
+    // plaintext values so they can be masked wherever the request is shown.
+    pub fn decrypt_input_options(&mut self, key: &SecretKey) -> Result<Vec<String>> {
+        let mut plaintexts = vec![];
+        for opt in self.input_options.iter_mut() {
+            let mut values = vec![];
+            for value in opt.values() {
+                values.push(match secret::is_encrypted(value) {
+                    true => {
+                        let plaintext = key.decrypt(value)?;
+                        plaintexts.push(plaintext.clone());
+                        plaintext
+                    }
+                    false => String::from(value),
+                });
+            }
+            opt.set_values(values.iter().map(|x| x.as_ref()).collect());
+        }
+        Ok(plaintexts)
+    }
+    // Replace encrypted option values with a mask, for displaying planned
+    // requests without the key
+    pub fn mask_input_options(&mut self) {
+        for opt in self.input_options.iter_mut() {
+            let values: Vec<String> = opt
+                .values()
+                .into_iter()
+                .map(|x| String::from(secret::mask(x)))
+                .collect();
+            opt.set_values(values.iter().map(|x| x.as_ref()).collect());
+        }
+    }
     pub fn output_options(&self) -> &Vec<OutputOption> {
         &self.output_options
     }
     pub fn consume_body(&mut self) -> Option<Vec<u8>> {
         self.body.take()
     }
-    pub fn variable_names(&self) -> HashSet<String> {
-        // find all variables in the request
-        // TODO: lazy static
-        let re = Regex::new(r"\{(.*?)\}").unwrap();
-        let mut names: Vec<String> = re
-            .captures_iter(&self.url)
-            .map(|cap| String::from(cap.get(1).unwrap().as_str()))
-            .collect();
-        if let Some(headers) = &self.headers {
-            let mut headers: Vec<String> = re
-                .captures_iter(&headers)
-                .map(|cap| String::from(cap.get(1).unwrap().as_str()))
-                .collect();
-            names.append(&mut headers);
-        }
-        if let Some(body) = &self.body {
-            let re = regex::bytes::Regex::new(r"\{(.*?)\}").unwrap();
-            let mut body: Vec<String> = re
-                .captures_iter(&body)
-                .map(|cap| String::from_utf8(cap.get(1).unwrap().as_bytes().to_vec()))
-                .filter_map(|x| x.ok())
-                .collect();
-            names.append(&mut body);
-        }
-
-        HashSet::from_iter(names.into_iter())
-    }
     pub fn set_input_option(&mut self, opt: &str, values: Vec<&str>) -> Result<()> {
         let opt = self
             .input_options
@@ -154,34 +308,152 @@ impl Request {
         opt.unwrap().set_values(values);
         Ok(())
     }
+    // Replace the input options and expand the built-in placeholders
     pub fn replace_input_options(&mut self) -> Result<()> {
-        // TODO: better replacement for all options
-        //       this could result in some unexpected behavior
-        //       will need to do a two pass approach:
-        //          1. find all start/end indices
-        //          2. iterate backwards to perform replacement
-        // find all variables and replace with values in options
+        self.substitute_input_options()?;
+        self.expand_placeholders()
+    }
+    // Replace the input options, leaving built-in placeholders such as
+    // {$uuid} as they are. Query parameters and headers holding an optional
+    // option without a value are dropped.
+    pub fn substitute_input_options(&mut self) -> Result<()> {
         let missing_opts: Vec<_> = self
             .input_options
             .iter()
-            .filter(|opt| opt.values().len() == 0)
+            .filter(|opt| opt.is_missing())
             .map(|opt| String::from(opt.option_name()))
             .collect();
-        if missing_opts.len() > 0 {
-            // All input options are required
+        if !missing_opts.is_empty() {
+            // Options without a default or '?' are required
             return Err(Error::new(ErrorKind::MissingOptions(missing_opts)));
         }
-        for opt in self.input_options.iter() {
-            if opt.values().len() == 0 {}
-            let old = format!("{{{}}}", opt.option_name());
-            let new = opt.values().remove(0);
-            self.url = self.url.replace(&old, &new);
-            self.headers = self.headers.as_ref().map(|h| h.replace(&old, &new));
-            if let Some(body) = &self.body {
-                let old = format!(r"\{{{}\}}", opt.option_name());
-                let re = regex::bytes::Regex::new(&old).unwrap();
-                self.body = Some(re.replace_all(&body, new.as_bytes()).to_vec());
+        let values: HashMap<String, Option<String>> = self
+            .input_options
+            .iter()
+            .map(|opt| {
+                let value = opt.effective_values().first().map(|x| String::from(*x));
+                (String::from(opt.option_name()), value)
+            })
+            .collect();
+
+        // replace all options in a single pass so values that look like
+        // options are not replaced again. Returns None for text that holds an
+        // optional option without a value when `drop_missing` is set, and
+        // leaves the option empty otherwise.
+        let re = Regex::new(InputOption::PATTERN).unwrap();
+        let render = |s: &str, drop_missing: bool| -> Option<String> {
+            let mut out = String::new();
+            let mut last = 0;
+            for cap in re.captures_iter(s) {
+                let m = cap.get(0).unwrap();
+                out.push_str(&s[last..m.start()]);
+                last = m.end();
+                match (values.get(&cap[2]), cap.get(1).is_some()) {
+                    // {?name} is a query parameter named after the option
+                    (Some(Some(value)), true) => {
+                        // query parameters already follow a '?'
+                        let in_query = drop_missing || out.contains('?');
+                        out.push(if in_query { '&' } else { '?' });
+                        out.push_str(&format!("{}={}", &cap[2], value));
+                    }
+                    (Some(Some(value)), false) => out.push_str(value),
+                    (Some(None), true) => (),
+                    (Some(None), false) if drop_missing => return None,
+                    (Some(None), false) => (),
+                    (None, _) => out.push_str(m.as_str()),
+                }
+            }
+            out.push_str(&s[last..]);
+            Some(out)
+        };
+
+        // find the '?' that starts the query, skipping the ones in options
+        // such as {name?} or {?name}. Options in the path are left empty.
+        let len = self.url.len();
+        let mut query_start = None;
+        let mut last = 0;
+        for (start, end) in re
+            .find_iter(&self.url)
+            .map(|m| (m.start(), m.end()))
+            .chain(std::iter::once((len, len)))
+        {
+            if let Some(i) = self.url[last..start].find('?') {
+                query_start = Some(last + i);
+                break;
+            }
+            last = end;
+        }
+        self.url = match query_start.map(|i| (&self.url[..i], &self.url[i + 1..])) {
+            Some((path, query)) => {
+                let mut url = render(path, false).unwrap_or_default();
+                let params: Vec<String> =
+                    query.split('&').filter_map(|x| render(x, true)).collect();
+                if !params.is_empty() {
+                    url.push(if url.contains('?') { '&' } else { '?' });
+                    url.push_str(&params.join("&"));
+                }
+                url
+            }
+            None => render(&self.url, false).unwrap_or_default(),
+        };
+        if let Some(headers) = &self.headers {
+            let headers: Vec<String> = headers
+                .split('\n')
+                .filter_map(|x| render(x, true))
+                .collect();
+            self.headers = match headers.len() {
+                0 => None,
+                _ => Some(headers.join("\n")),
+            };
+        }
+        self.auth = self
+            .auth
+            .as_ref()
+            .map(|a| a.map_values(|v| render(v, false).unwrap_or_default()));
+        if let Some(body) = &self.body {
+            self.body = Some(match std::str::from_utf8(body) {
+                Ok(body) => render(body, false).unwrap_or_default().into_bytes(),
+                Err(_) => {
+                    let re = regex::bytes::Regex::new(InputOption::PATTERN).unwrap();
+                    re.replace_all(body, |cap: &regex::bytes::Captures| {
+                        let name = String::from_utf8_lossy(&cap[2]).into_owned();
+                        match values.get(&name) {
+                            Some(value) => value.clone().unwrap_or_default().into_bytes(),
+                            None => cap[0].to_vec(),
+                        }
+                    })
+                    .into_owned()
+                }
+            });
+        }
+        Ok(())
+    }
+    pub fn expand_placeholders(&mut self) -> Result<()> {
+        let mut placeholders = Placeholders::new();
+        self.url = placeholders.expand(&self.url)?;
+        if let Some(headers) = &self.headers {
+            self.headers = Some(placeholders.expand(headers)?);
+        }
+        if let Some(auth) = &self.auth {
+            let mut error = None;
+            let auth = auth.map_values(|v| {
+                placeholders.expand(v).unwrap_or_else(|x| {
+                    error = Some(x);
+                    String::from(v)
+                })
+            });
+            if let Some(x) = error {
+                return Err(x);
             }
+            self.auth = Some(auth);
+        }
+        // bodies that are not text are sent as they are
+        let body = self
+            .body
+            .as_ref()
+            .and_then(|x| String::from_utf8(x.clone()).ok());
+        if let Some(body) = body {
+            self.body = Some(placeholders.expand(&body)?.into_bytes());
         }
         Ok(())
     }
@@ -190,14 +462,16 @@ impl Request {
 impl DbObject for Request {
     fn create(&self, conn: &Connection) -> Result<()> {
         conn.execute(
-            "INSERT INTO requests (name, method, url, headers, body)
-                  VALUES (?1, ?2, ?3, ?4, ?5);",
+            "INSERT INTO requests (name, method, url, headers, body, combination, auth)
+                  VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
             params![
                 self.name,
                 self.method.to_string(),
                 self.url,
                 self.headers,
-                self.body
+                self.body,
+                self.combination,
+                self.auth.as_ref().map(|x| x.to_auth_string())
             ],
         )?;
         // create input options
@@ -217,28 +491,48 @@ impl DbObject for Request {
         for option in self.output_options.iter() {
             option.delete(conn)?;
         }
+        for assertion in Assertion::get_by_name(conn, &self.name)? {
+            assertion.delete(conn)?;
+        }
+        for config in Config::get_by(conn, |x| x.request_name() == Some(&self.name))? {
+            config.delete(conn)?;
+        }
         conn.execute("DELETE FROM requests WHERE name = ?1;", params![self.name])?;
         Ok(())
     }
     fn update(&self, conn: &Connection) -> Result<usize> {
-        // TODO: update input/output options
         let num = conn.execute(
-            "UPDATE requests SET method = ?2, url = ?3, headers = ?4, body = ?5 WHERE name = ?1;",
+            "UPDATE requests SET
+                method = ?2, url = ?3, headers = ?4, body = ?5, combination = ?6, auth = ?7
+            WHERE name = ?1;",
             params![
                 self.name,
                 self.method.to_string(),
                 self.url,
                 self.headers,
-                self.body
+                self.body,
+                self.combination,
+                self.auth.as_ref().map(|x| x.to_auth_string())
             ],
         )?;
+        // remove input options that are no longer used by the request
+        for option in InputOption::get_by_name(conn, &self.name)? {
+            if !self
+                .input_options
+                .iter()
+                .any(|x| x.option_name() == option.option_name())
+            {
+                option.delete(conn)?;
+            }
+        }
         for option in self.input_options.iter() {
-            option.update(conn)?;
+            option.upsert(conn)?;
         }
         Ok(num)
     }
     fn get_all(conn: &Connection) -> Result<Vec<Request>> {
-        let mut stmt = conn.prepare("SELECT name, method, url, headers, body FROM requests;")?;
+        let mut stmt = conn
+            .prepare("SELECT name, method, url, headers, body, combination, auth FROM requests;")?;
 
         let requests = stmt.query_map(NO_PARAMS, |row| {
             let name: String = row.get(0)?;
@@ -252,6 +546,10 @@ impl DbObject for Request {
                 url: row.get(2)?,
                 headers: row.get(3)?,
                 body: row.get(4)?,
+                combination: row.get(5)?,
+                auth: row
+                    .get::<_, Option<String>>(6)?
+                    .and_then(|x| Auth::parse(&x).ok()),
 
                 input_options: input_opts.unwrap(),
                 output_options: output_opts.unwrap(),
@@ -274,6 +572,7 @@ impl PrintableTableStruct for Request {
             Cell::new("url"),
             Cell::new("headers"),
             Cell::new("body?"),
+            Cell::new("auth"),
         ]
     }
     fn get_rows(&self) -> Vec<Vec<Cell>> {
@@ -284,7 +583,7 @@ impl PrintableTableStruct for Request {
                 "false"
             }
         };
-        let can_run = self.input_options.iter().all(|x| x.values().len() > 0);
+        let can_run = self.input_options.iter().all(|x| !x.is_missing());
         let mut name = Cell::new(&self.name);
         if can_run {
             name = name.fg(Color::Green);
@@ -295,6 +594,112 @@ impl PrintableTableStruct for Request {
             Cell::new(&self.url),
             Cell::new(self.headers.as_ref().unwrap_or(&String::from(""))),
             Cell::new(has_body),
+            Cell::new(self.auth.as_ref().map(Auth::summary).unwrap_or_default()),
         ]]
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn option_names(request: &Request) -> Vec<&str> {
+        let mut names: Vec<&str> = request
+            .input_options()
+            .iter()
+            .map(|x| x.option_name())
+            .collect();
+        names.sort_unstable();
+        names
+    }
+
+    #[test]
+    fn options_from_url_headers_and_body() {
+        let mut request = Request::new("get-x", None, "http://{host}/x");
+        request.add_header("Authorization", "Bearer {token}");
+        request.add_query_param("page={page}");
+        request.set_body(Some(b"id={id}".to_vec()));
+        assert_eq!(option_names(&request), vec!["host", "id", "page", "token"]);
+    }
+
+    #[test]
+    fn json_body_is_not_an_option() {
+        let mut request = Request::new("create-x", None, "http://localhost/x");
+        request.set_body(Some(br#"{"id": 1, "tags": {}, "name": "{name}"}"#.to_vec()));
+        assert_eq!(option_names(&request), vec!["name"]);
+    }
+
+    #[test]
+    fn option_names_with_dots_and_dashes() {
+        let request = Request::new("get-x", None, "http://{api.host}/{user-id}/{a b}");
+        assert_eq!(option_names(&request), vec!["api.host", "user-id"]);
+    }
+    fn substitute(request: &mut Request, values: &[(&str, &str)]) -> Result<()> {
+        for (name, value) in values {
+            request.set_input_option(name, vec![value])?;
+        }
+        request.substitute_input_options()
+    }
+
+    #[test]
+    fn option_defaults() {
+        let mut request = Request::new("get-x", None, "http://{host:localhost}/{id:1}");
+        request.set_body(Some(b"limit={limit:10}".to_vec()));
+        substitute(&mut request, &[("id", "2")]).unwrap();
+        assert_eq!(request.url(), "http://localhost/2");
+        assert_eq!(request.body().as_deref(), Some(&b"limit=10"[..]));
+    }
+
+    #[test]
+    fn required_options_are_missing() {
+        let mut request = Request::new("get-x", None, "http://localhost/{id}?q={q?}");
+        let err = request.substitute_input_options().unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            Error::new(ErrorKind::MissingOptions(vec![String::from("id")])).to_string()
+        );
+    }
+
+    #[test]
+    fn missing_optional_query_param() {
+        let url = "http://localhost/{dir?}/x?a={a?}&b={b}&c=3";
+        let mut request = Request::new("get-x", None, url);
+        substitute(&mut request, &[("b", "2")]).unwrap();
+        assert_eq!(request.url(), "http://localhost//x?b=2&c=3");
+
+        let mut request = Request::new("get-x", None, "http://localhost/x?a={a?}");
+        substitute(&mut request, &[]).unwrap();
+        assert_eq!(request.url(), "http://localhost/x");
+    }
+
+    #[test]
+    fn missing_optional_header() {
+        let mut request = Request::new("get-x", None, "http://localhost/x");
+        request.add_header("X-A", "{a?}");
+        request.add_header("X-B", "{b:2}");
+        substitute(&mut request, &[]).unwrap();
+        assert_eq!(request.headers().as_deref(), Some("X-B: 2"));
+    }
+
+    #[test]
+    fn query_param_options() {
+        let mut request = Request::new("get-x", None, "http://localhost/x{?page}{?size}");
+        substitute(&mut request, &[("page", "1"), ("size", "10")]).unwrap();
+        assert_eq!(request.url(), "http://localhost/x?page=1&size=10");
+
+        let mut request = Request::new("get-x", None, "http://localhost/x{?page}{?size}");
+        substitute(&mut request, &[("size", "10")]).unwrap();
+        assert_eq!(request.url(), "http://localhost/x?size=10");
+
+        let mut request = Request::new("get-x", None, "http://localhost/x?a=1{?page}");
+        substitute(&mut request, &[("page", "2")]).unwrap();
+        assert_eq!(request.url(), "http://localhost/x?a=1&page=2");
+    }
+
+    #[test]
+    fn values_are_not_substituted_again() {
+        let mut request = Request::new("get-x", None, "http://localhost/{a}/{b}");
+        substitute(&mut request, &[("a", "{b}"), ("b", "x")]).unwrap();
+        assert_eq!(request.url(), "http://localhost/{b}/x");
+    }
+}
@@ -1,12 +1,31 @@
-use super::{DbObject, PrintableTableStruct};
+use super::auth::MASK;
+use super::{Auth, DbObject, PrintableTableStruct};
 use crate::error::{Error, ErrorKind, Result};
+use chrono::{DateTime, Duration, Utc};
 use comfy_table::{Attribute, Cell, Color};
+use percent_encoding::{utf8_percent_encode, AsciiSet, CONTROLS, NON_ALPHANUMERIC};
 use reqwest::blocking;
-use reqwest::Method;
-use rusqlite::{params, Connection, NO_PARAMS};
+use reqwest::header::HeaderMap;
+use reqwest::{Method, StatusCode, Url};
+use rusqlite::types::ToSql;
+use rusqlite::{params, Connection, Row, NO_PARAMS};
+
+// Characters that are percent-encoded in the query and path of a URL, and
+// the ones left as they are in form data
+const QUERY_SET: &AsciiSet = &CONTROLS.add(b' ').add(b'"').add(b'#').add(b'<').add(b'>');
+const SPECIAL_QUERY_SET: &AsciiSet = &QUERY_SET.add(b'\'');
+const PATH_SET: &AsciiSet = &QUERY_SET.add(b'?').add(b'`').add(b'{').add(b'}');
+const FORM_SET: &AsciiSet = &NON_ALPHANUMERIC
+    .remove(b'*')
+    .remove(b'-')
+    .remove(b'.')
+    .remove(b'_');
 
 pub struct RequestResponse {
     rowid: u32,
+    request_name: Option<String>,
+    environment: Option<String>,
+    timestamp: Option<String>,
     request_url: String,
     request_method: Method,
     request_headers: Option<String>,
@@ -15,14 +34,42 @@ pub struct RequestResponse {
     response_headers: Option<String>,
     response_body: Option<Vec<u8>>,
     response_extractions: Vec<(String, String)>,
+    request_options: Vec<(String, String)>,
+    assertion_results: Vec<(String, String)>,
+    // timings in milliseconds and body sizes in bytes
+    duration_ms: Option<f64>,
+    ttfb_ms: Option<f64>,
+    request_size: Option<u32>,
+    response_size: Option<u32>,
 }
 
 impl RequestResponse {
     const VALUE_SEPARATOR: &'static str = "\n~\n";
+    const COLUMNS: &'static str = "rowid,
+                    request_name,
+                    environment,
+                    timestamp,
+                    request_url,
+                    request_method,
+                    request_headers,
+                    request_body,
+                    response_status,
+                    response_headers,
+                    response_body,
+                    response_extractions,
+                    request_options,
+                    assertion_results,
+                    duration_ms,
+                    ttfb_ms,
+                    request_size,
+                    response_size";
 
-    pub fn new(req: &blocking::Request) -> RequestResponse {
+    pub fn new(name: &str, env: Option<&str>, req: &blocking::Request) -> RequestResponse {
         RequestResponse {
             rowid: 0,
+            request_name: Some(String::from(name)),
+            environment: env.map(String::from),
+            timestamp: Some(format!("{}", Utc::now().format("%Y-%m-%d %T %Z"))),
             request_url: format!("{}", req.url()),
             request_method: req.method().clone(),
             request_headers: Some(
@@ -33,30 +80,137 @@ impl RequestResponse {
                     .join("\n"),
             ),
             request_body: req.body().map(|x| x.as_bytes().unwrap().to_vec()),
+            request_size: Some(req.body().and_then(|x| x.as_bytes()).map_or(0, |x| x.len()) as u32),
 
             response_status: None,
             response_headers: None,
             response_body: None,
             response_extractions: vec![],
+            request_options: vec![],
+            assertion_results: vec![],
+            duration_ms: None,
+            ttfb_ms: None,
+            response_size: None,
         }
     }
-    pub fn set_response(&mut self, resp: &blocking::Response, body: &Vec<u8>) {
-        self.response_status = Some(format!("{}", resp.status()));
+    pub fn set_response(&mut self, status: StatusCode, headers: &HeaderMap, body: &[u8]) {
+        self.response_status = Some(format!("{}", status));
         self.response_headers = Some(
-            resp.headers()
+            headers
                 .iter()
                 .map(|x| format!("{}: {}", x.0, x.1.to_str().unwrap()))
                 .collect::<Vec<_>>()
                 .join("\n"),
         );
 
-        self.response_body = Some(body.clone());
+        self.response_body = Some(body.to_vec());
+        self.response_size = Some(body.len() as u32);
+    }
+    // ttfb is the time until the response headers arrived
+    pub fn set_timing(
+        &mut self,
+        sent: DateTime<Utc>,
+        ttfb: std::time::Duration,
+        duration: std::time::Duration,
+    ) {
+        self.timestamp = Some(format!("{}", sent.format("%Y-%m-%d %T %Z")));
+        self.ttfb_ms = Some(ttfb.as_secs_f64() * 1000.0);
+        self.duration_ms = Some(duration.as_secs_f64() * 1000.0);
+    }
+    // Hide the credentials added by auth
+    pub fn mask_auth(&mut self, auth: &Auth) {
+        if let (Some(name), Some(headers)) = (auth.header(), &self.request_headers) {
+            let headers: Vec<String> = headers
+                .split('\n')
+                .map(|header| {
+                    let mut items = header.splitn(2, ':');
+                    let key = items.next().unwrap_or("");
+                    if !key.trim().eq_ignore_ascii_case(name) {
+                        return String::from(header);
+                    }
+                    // keep the scheme of an authorization header
+                    match auth {
+                        Auth::Basic(_, _) => format!("{}: Basic {}", key, MASK),
+                        Auth::Bearer(_) | Auth::OAuth2(_) => format!("{}: Bearer {}", key, MASK),
+                        _ => format!("{}: {}", key, MASK),
+                    }
+                })
+                .collect();
+            self.request_headers = Some(headers.join("\n"));
+        }
+        if let Auth::ApiKeyQuery(name, _) = auth {
+            if let Ok(mut url) = Url::parse(&self.request_url) {
+                let pairs: Vec<(String, String)> = url
+                    .query_pairs()
+                    .map(|(k, v)| match k == name.as_str() {
+                        true => (k.into_owned(), String::from(MASK)),
+                        false => (k.into_owned(), v.into_owned()),
+                    })
+                    .collect();
+                url.query_pairs_mut().clear().extend_pairs(pairs);
+                self.request_url = url.to_string();
+            }
+        }
+    }
+    // Hide the values of secret variables wherever they were substituted
+    pub fn mask_values(&mut self, values: &[String]) {
+        self.request_url = RequestResponse::mask_str(&self.request_url, values);
+        if let Some(headers) = &self.request_headers {
+            self.request_headers = Some(RequestResponse::mask_str(headers, values));
+        }
+        let body = self
+            .request_body
+            .as_ref()
+            .and_then(|x| String::from_utf8(x.clone()).ok());
+        if let Some(body) = body {
+            self.request_body = Some(RequestResponse::mask_str(&body, values).into_bytes());
+        }
+    }
+    // Mask the values in s, including the forms they take when they are
+    // percent-encoded in a URL or form data
+    pub fn mask_str(s: &str, values: &[String]) -> String {
+        let mut s = String::from(s);
+        for value in values.iter().filter(|x| !x.is_empty()) {
+            let encoded = [QUERY_SET, SPECIAL_QUERY_SET, PATH_SET, NON_ALPHANUMERIC]
+                .iter()
+                .map(|set| utf8_percent_encode(value, set).to_string())
+                .chain(Some(
+                    utf8_percent_encode(value, FORM_SET)
+                        .to_string()
+                        .replace("%20", "+"),
+                ));
+            for form in Some(value.clone()).into_iter().chain(encoded) {
+                s = s.replace(&form, MASK);
+            }
+        }
+        s
+    }
+    pub fn add_input_option(&mut self, key: &str, value: &str) {
+        self.request_options
+            .push((String::from(key), String::from(value)));
+    }
+    pub fn add_assertion_result(&mut self, description: &str, failure: Option<&str>) {
+        let result = match failure {
+            None => String::from("pass"),
+            Some(x) => format!("fail: {}", x),
+        };
+        self.assertion_results
+            .push((String::from(description), result));
     }
     pub fn add_extraction(&mut self, key: &str, value: &str) {
         self.response_extractions
             .push((String::from(key), String::from(value)));
     }
 
+    pub fn request_name(&self) -> Option<&str> {
+        self.request_name.as_deref()
+    }
+    pub fn environment(&self) -> Option<&str> {
+        self.environment.as_deref()
+    }
+    pub fn timestamp(&self) -> Option<&str> {
+        self.timestamp.as_deref()
+    }
     pub fn url(&self) -> &str {
         self.request_url.as_ref()
     }
@@ -84,6 +238,30 @@ impl RequestResponse {
     pub fn response_body(&self) -> Option<&Vec<u8>> {
         self.response_body.as_ref()
     }
+    pub fn input_options(&self) -> Vec<(&str, &str)> {
+        self.request_options
+            .iter()
+            .map(|x| (x.0.as_ref(), x.1.as_ref()))
+            .collect()
+    }
+    pub fn assertion_results(&self) -> Vec<(&str, &str)> {
+        self.assertion_results
+            .iter()
+            .map(|x| (x.0.as_ref(), x.1.as_ref()))
+            .collect()
+    }
+    pub fn duration_ms(&self) -> Option<f64> {
+        self.duration_ms
+    }
+    pub fn ttfb_ms(&self) -> Option<f64> {
+        self.ttfb_ms
+    }
+    pub fn request_size(&self) -> Option<u32> {
+        self.request_size
+    }
+    pub fn response_size(&self) -> Option<u32> {
+        self.response_size
+    }
     pub fn extractions(&self) -> Vec<(&str, &str)> {
         self.response_extractions
             .iter()
@@ -95,6 +273,9 @@ impl RequestResponse {
         conn.execute(
             "CREATE TABLE IF NOT EXISTS request_responses (
                   rowid                 INTEGER PRIMARY KEY,
+                  request_name          TEXT,
+                  environment           TEXT,
+                  timestamp             TEXT,
                   request_url           TEXT NOT NULL,
                   request_method        TEXT NOT NULL,
                   request_headers       TEXT,
@@ -102,46 +283,42 @@ impl RequestResponse {
                   response_status       TEXT,
                   response_headers      TEXT,
                   response_body         BLOB,
-                  response_extractions  TEXT
+                  response_extractions  TEXT,
+                  request_options       TEXT,
+                  assertion_results     TEXT,
+                  duration_ms           REAL,
+                  ttfb_ms               REAL,
+                  request_size          INTEGER,
+                  response_size         INTEGER
               )",
             NO_PARAMS,
         )?;
+        // workspaces created before responses were kept as history
+        super::db::add_missing_columns(
+            conn,
+            "request_responses",
+            &[
+                ("request_name", "TEXT"),
+                ("environment", "TEXT"),
+                ("timestamp", "TEXT"),
+                ("request_options", "TEXT"),
+                ("assertion_results", "TEXT"),
+                ("duration_ms", "REAL"),
+                ("ttfb_ms", "REAL"),
+                ("request_size", "INTEGER"),
+                ("response_size", "INTEGER"),
+            ],
+        )?;
         Ok(())
     }
 
-    pub fn delete_all(conn: &Connection) -> Result<()> {
-        conn.execute("DELETE FROM request_responses;", NO_PARAMS)?;
-        Ok(())
-    }
     pub fn get_by_id(conn: &Connection, id: u32) -> Result<RequestResponse> {
-        let mut stmt = conn.prepare(
-            "SELECT
-                    rowid,
-                    request_url,
-                    request_method,
-                    request_headers,
-                    request_body,
-                    response_status,
-                    response_headers,
-                    response_body,
-                    response_extractions
-                FROM request_responses WHERE rowid = ?1;",
-        )?;
+        let mut stmt = conn.prepare(&format!(
+            "SELECT {} FROM request_responses WHERE rowid = ?1;",
+            RequestResponse::COLUMNS
+        ))?;
 
-        let req_resps = stmt.query_map(params![id], |row| {
-            let s: String = row.get(2)?;
-            Ok(RequestResponse {
-                rowid: row.get(0)?,
-                request_url: row.get(1)?,
-                request_method: Method::from_bytes(s.as_bytes()).unwrap_or(Method::GET),
-                request_headers: row.get(3)?,
-                request_body: row.get(4)?,
-                response_status: row.get(5)?,
-                response_headers: row.get(6)?,
-                response_body: row.get(7)?,
-                response_extractions: RequestResponse::unstringify_extractions(row.get(8)?),
-            })
-        })?;
+        let req_resps = stmt.query_map(params![id], RequestResponse::from_row)?;
 
         // TODO: print a warning for errors
         let mut v: Vec<_> = req_resps.filter_map(|req| req.ok()).collect();
@@ -151,8 +328,98 @@ impl RequestResponse {
             Ok(v.remove(0))
         }
     }
+    pub fn get_history(
+        conn: &Connection,
+        request: Option<&str>,
+        environment: Option<&str>,
+        status: Option<&str>,
+        limit: Option<u32>,
+    ) -> Result<Vec<RequestResponse>> {
+        let mut conditions = vec![];
+        let mut values: Vec<&dyn ToSql> = vec![];
+        if let Some(request) = &request {
+            conditions.push("request_name = ?");
+            values.push(request);
+        }
+        if let Some(environment) = &environment {
+            conditions.push("environment = ?");
+            values.push(environment);
+        }
+        // a status class like 5xx matches any status starting with 5
+        let status = status.map(|x| format!("{}%", x.trim_end_matches(['x', 'X'])));
+        if let Some(status) = &status {
+            conditions.push("response_status LIKE ?");
+            values.push(status);
+        }
+        let condition = match conditions.len() {
+            0 => String::new(),
+            _ => format!("WHERE {}", conditions.join(" AND ")),
+        };
+        let limit = limit.map(|x| x as i64).unwrap_or(-1);
+
+        // select the most recent rows and return them in chronological order
+        let mut stmt = conn.prepare(&format!(
+            "SELECT * FROM (
+                SELECT {} FROM request_responses {} ORDER BY rowid DESC LIMIT {}
+            ) ORDER BY rowid ASC;",
+            RequestResponse::COLUMNS,
+            condition,
+            limit
+        ))?;
+        let req_resps = stmt.query_map(values, RequestResponse::from_row)?;
+
+        // TODO: print a warning for errors
+        Ok(req_resps.filter_map(|req| req.ok()).collect())
+    }
+    pub fn prune(
+        conn: &Connection,
+        max_rows: Option<u32>,
+        max_age: Option<Duration>,
+    ) -> Result<()> {
+        if let Some(max_rows) = max_rows {
+            conn.execute(
+                "DELETE FROM request_responses WHERE rowid NOT IN (
+                    SELECT rowid FROM request_responses ORDER BY rowid DESC LIMIT ?1
+                );",
+                params![max_rows],
+            )?;
+        }
+        if let Some(max_age) = max_age {
+            let cutoff = Utc::now() - max_age;
+            // rows kept before timestamps were recorded are the oldest
+            conn.execute(
+                "DELETE FROM request_responses WHERE timestamp IS NULL OR timestamp < ?1;",
+                params![format!("{}", cutoff.format("%Y-%m-%d %T %Z"))],
+            )?;
+        }
+        Ok(())
+    }
 
-    fn stringify_extractions(v: &Vec<(String, String)>) -> Option<String> {
+    fn from_row(row: &Row) -> rusqlite::Result<RequestResponse> {
+        let s: String = row.get(5)?;
+        Ok(RequestResponse {
+            rowid: row.get(0)?,
+            request_name: row.get(1)?,
+            environment: row.get(2)?,
+            timestamp: row.get(3)?,
+            request_url: row.get(4)?,
+            request_method: Method::from_bytes(s.as_bytes()).unwrap_or(Method::GET),
+            request_headers: row.get(6)?,
+            request_body: row.get(7)?,
+            response_status: row.get(8)?,
+            response_headers: row.get(9)?,
+            response_body: row.get(10)?,
+            response_extractions: RequestResponse::unstringify_pairs(row.get(11)?),
+            request_options: RequestResponse::unstringify_pairs(row.get(12)?),
+            assertion_results: RequestResponse::unstringify_pairs(row.get(13)?),
+            duration_ms: row.get(14)?,
+            ttfb_ms: row.get(15)?,
+            request_size: row.get(16)?,
+            response_size: row.get(17)?,
+        })
+    }
+
+    fn stringify_pairs(v: &[(String, String)]) -> Option<String> {
         match v.len() {
             0 => None,
             _ => Some(
@@ -163,12 +430,12 @@ impl RequestResponse {
             ),
         }
     }
-    fn unstringify_extractions(v: Option<String>) -> Vec<(String, String)> {
+    fn unstringify_pairs(v: Option<String>) -> Vec<(String, String)> {
         match v {
             Some(v) => v
                 .split(RequestResponse::VALUE_SEPARATOR)
                 .map(|x| {
-                    let mut iter = x.split(" <= ");
+                    let mut iter = x.splitn(2, " <= ");
                     (
                         String::from(iter.next().unwrap()),
                         String::from(iter.next().unwrap()),
@@ -184,6 +451,9 @@ impl DbObject for RequestResponse {
     fn create(&self, conn: &Connection) -> Result<()> {
         conn.execute(
             "INSERT INTO request_responses (
+                    request_name,
+                    environment,
+                    timestamp,
                     request_url,
                     request_method,
                     request_headers,
@@ -191,10 +461,19 @@ impl DbObject for RequestResponse {
                     response_status,
                     response_headers,
                     response_body,
-                    response_extractions
+                    response_extractions,
+                    request_options,
+                    assertion_results,
+                    duration_ms,
+                    ttfb_ms,
+                    request_size,
+                    response_size
                   )
-              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8);",
+              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17);",
             params![
+                &self.request_name,
+                &self.environment,
+                &self.timestamp,
                 &self.request_url,
                 &self.request_method.to_string(),
                 &self.request_headers,
@@ -202,7 +481,13 @@ impl DbObject for RequestResponse {
                 &self.response_status,
                 &self.response_headers,
                 &self.response_body,
-                RequestResponse::stringify_extractions(&self.response_extractions),
+                RequestResponse::stringify_pairs(&self.response_extractions),
+                RequestResponse::stringify_pairs(&self.request_options),
+                RequestResponse::stringify_pairs(&self.assertion_results),
+                &self.duration_ms,
+                &self.ttfb_ms,
+                &self.request_size,
+                &self.response_size,
             ],
         )?;
         Ok(())
@@ -219,46 +504,25 @@ impl DbObject for RequestResponse {
         Ok(0)
     }
     fn get_all(conn: &Connection) -> Result<Vec<RequestResponse>> {
-        let mut stmt = conn.prepare(
-            "SELECT
-                    rowid,
-                    request_url,
-                    request_method,
-                    request_headers,
-                    request_body,
-                    response_status,
-                    response_headers,
-                    response_body,
-                    response_extractions
-                FROM request_responses;",
-        )?;
-
-        let req_resps = stmt.query_map(NO_PARAMS, |row| {
-            let s: String = row.get(2)?;
-            Ok(RequestResponse {
-                rowid: row.get(0)?,
-                request_url: row.get(1)?,
-                request_method: Method::from_bytes(s.as_bytes()).unwrap_or(Method::GET),
-                request_headers: row.get(3)?,
-                request_body: row.get(4)?,
-                response_status: row.get(5)?,
-                response_headers: row.get(6)?,
-                response_body: row.get(7)?,
-                response_extractions: RequestResponse::unstringify_extractions(row.get(8)?),
-            })
-        })?;
-
-        // TODO: print a warning for errors
-        Ok(req_resps.filter_map(|req| req.ok()).collect())
+        RequestResponse::get_history(conn, None, None, None, None)
     }
     fn name(&self) -> &str {
-        ""
+        self.request_name().unwrap_or("")
     }
 }
 
 impl PrintableTableStruct for RequestResponse {
     fn get_header() -> Vec<Cell> {
-        vec![Cell::new("id"), Cell::new("request"), Cell::new("status")]
+        vec![
+            Cell::new("id"),
+            Cell::new("name"),
+            Cell::new("environment"),
+            Cell::new("request"),
+            Cell::new("status"),
+            Cell::new("time"),
+            Cell::new("size"),
+            Cell::new("timestamp"),
+        ]
     }
     fn get_rows(&self) -> Vec<Vec<Cell>> {
         let status = self.response_status.clone().unwrap_or(String::from("-"));
@@ -273,12 +537,85 @@ impl PrintableTableStruct for RequestResponse {
         };
         vec![vec![
             Cell::new(&self.rowid),
+            Cell::new(self.request_name().unwrap_or("")),
+            Cell::new(self.environment().unwrap_or("")),
             Cell::new(format!(
                 "{} {}",
                 self.request_method.to_string(),
                 self.request_url
             )),
             status,
+            Cell::new(format_ms(self.duration_ms)),
+            Cell::new(format_size(self.response_size)),
+            Cell::new(self.timestamp().unwrap_or("")),
         ]]
     }
 }
+
+pub fn format_ms(ms: Option<f64>) -> String {
+    match ms {
+        Some(ms) if ms >= 1000.0 => format!("{:.2}s", ms / 1000.0),
+        Some(ms) => format!("{:.0}ms", ms),
+        None => String::from("-"),
+    }
+}
+
+pub fn format_size(bytes: Option<u32>) -> String {
+    match bytes {
+        Some(b) if b >= 1024 * 1024 => format!("{:.1} MiB", b as f64 / (1024.0 * 1024.0)),
+        Some(b) if b >= 1024 => format!("{:.1} KiB", b as f64 / 1024.0),
+        Some(b) => format!("{} B", b),
+        None => String::from("-"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn timestamps(conn: &Connection) -> Vec<Option<String>> {
+        let mut stmt = conn
+            .prepare("SELECT timestamp FROM request_responses ORDER BY rowid;")
+            .unwrap();
+        let rows = stmt.query_map(NO_PARAMS, |row| row.get(0)).unwrap();
+        rows.map(|x| x.unwrap()).collect()
+    }
+
+    #[test]
+    fn prune_by_age() {
+        let conn = Connection::open_in_memory().unwrap();
+        RequestResponse::create_table(&conn).unwrap();
+        let now = format!("{}", Utc::now().format("%Y-%m-%d %T %Z"));
+        for timestamp in &[None, Some("2020-01-01 00:00:00 UTC"), Some(now.as_str())] {
+            conn.execute(
+                "INSERT INTO request_responses (timestamp, request_url, request_method)
+                    VALUES (?1, 'http://localhost', 'GET');",
+                params![timestamp],
+            )
+            .unwrap();
+        }
+
+        RequestResponse::prune(&conn, None, Some(Duration::days(1))).unwrap();
+        assert_eq!(timestamps(&conn), vec![Some(now)]);
+    }
+
+    #[test]
+    fn prune_by_rows() {
+        let conn = Connection::open_in_memory().unwrap();
+        RequestResponse::create_table(&conn).unwrap();
+        for i in 0..5 {
+            conn.execute(
+                "INSERT INTO request_responses (timestamp, request_url, request_method)
+                    VALUES (?1, 'http://localhost', 'GET');",
+                params![i.to_string()],
+            )
+            .unwrap();
+        }
+
+        RequestResponse::prune(&conn, Some(2), None).unwrap();
+        assert_eq!(
+            timestamps(&conn),
+            vec![Some(String::from("3")), Some(String::from("4"))]
+        );
+    }
+}
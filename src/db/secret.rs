@@ -0,0 +1,73 @@
+use super::auth::MASK;
+use crate::error::{Error, ErrorKind, Result};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use sha2::{Digest, Sha256};
+
+// Encrypted values are stored as PREFIX followed by the base64 encoded nonce
+// and ciphertext, so they can be recognized wherever they are copied to.
+const PREFIX: &str = "secret:";
+const NONCE_SIZE: usize = 12;
+const PBKDF2_ROUNDS: u32 = 100_000;
+
+// The key that encrypts the secret variables of a workspace
+pub struct SecretKey([u8; 32]);
+
+impl SecretKey {
+    pub fn from_passphrase(passphrase: &str, salt: &[u8]) -> SecretKey {
+        let mut key = [0u8; 32];
+        pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, PBKDF2_ROUNDS, &mut key);
+        SecretKey(key)
+    }
+    pub fn from_key_file(contents: &[u8]) -> SecretKey {
+        SecretKey(Sha256::digest(contents).into())
+    }
+
+    pub fn encrypt(&self, plaintext: &str) -> String {
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.0));
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        // We can unwrap because encryption only fails for oversized input
+        let mut data = cipher.encrypt(&nonce, plaintext.as_bytes()).unwrap();
+        data.splice(0..0, nonce.iter().cloned());
+        format!("{}{}", PREFIX, base64::encode(&data))
+    }
+    pub fn decrypt(&self, value: &str) -> Result<String> {
+        let wrong_key = || {
+            Error::new(ErrorKind::ArgumentError(
+                "Could not decrypt a secret. Wrong passphrase or key file?",
+            ))
+        };
+        if !is_encrypted(value) {
+            return Err(wrong_key());
+        }
+        let data = base64::decode(&value[PREFIX.len()..]).map_err(|_| wrong_key())?;
+        if data.len() < NONCE_SIZE {
+            return Err(wrong_key());
+        }
+        let cipher = ChaCha20Poly1305::new(Key::from_slice(&self.0));
+        let (nonce, ciphertext) = data.split_at(NONCE_SIZE);
+        let plaintext = cipher
+            .decrypt(Nonce::from_slice(nonce), ciphertext)
+            .map_err(|_| wrong_key())?;
+        String::from_utf8(plaintext).map_err(|_| wrong_key())
+    }
+}
+
+pub fn is_encrypted(value: &str) -> bool {
+    value.starts_with(PREFIX)
+}
+
+// The value to display in place of a possibly encrypted value
+pub fn mask(value: &str) -> &str {
+    match is_encrypted(value) {
+        true => MASK,
+        false => value,
+    }
+}
+
+pub fn random_salt() -> String {
+    let mut salt = [0u8; 16];
+    OsRng.fill_bytes(&mut salt);
+    base64::encode(salt)
+}
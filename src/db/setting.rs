@@ -0,0 +1,92 @@
+use super::DbObject;
+use super::PrintableTableStruct;
+use crate::error::Result;
+use comfy_table::Cell;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+pub struct Setting {
+    key: String,
+    value: String,
+}
+
+impl Setting {
+    pub const HISTORY_MAX_ROWS: &'static str = "history.max-rows";
+    pub const HISTORY_MAX_AGE: &'static str = "history.max-age";
+    // salt of the passphrase and a known value to check the key against
+    pub const SECRET_SALT: &'static str = "secret.salt";
+    pub const SECRET_CHECK: &'static str = "secret.check";
+
+    pub fn new(key: &str, value: &str) -> Setting {
+        Setting {
+            key: String::from(key),
+            value: String::from(value),
+        }
+    }
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS settings (
+                  key             TEXT PRIMARY KEY,
+                  value           TEXT NOT NULL
+              )",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+
+    pub fn key(&self) -> &str {
+        self.key.as_ref()
+    }
+
+    pub fn get_value(conn: &Connection, key: &str) -> Result<Option<String>> {
+        Ok(Setting::get_by_name(conn, key)?
+            .into_iter()
+            .next()
+            .map(|x| x.value))
+    }
+}
+
+impl DbObject for Setting {
+    fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO settings (key, value) VALUES (?1, ?2);",
+            params![self.key, self.value],
+        )?;
+        Ok(())
+    }
+    fn delete(&self, conn: &Connection) -> Result<()> {
+        conn.execute("DELETE FROM settings WHERE key = ?1;", params![self.key])?;
+        Ok(())
+    }
+    fn update(&self, conn: &Connection) -> Result<usize> {
+        let num = conn.execute(
+            "UPDATE settings SET value = ?2 WHERE key = ?1;",
+            params![self.key, self.value],
+        )?;
+        Ok(num)
+    }
+    fn get_all(conn: &Connection) -> Result<Vec<Setting>> {
+        let mut stmt = conn.prepare("SELECT key, value FROM settings ORDER BY key;")?;
+
+        let settings = stmt.query_map(NO_PARAMS, |row| {
+            Ok(Setting {
+                key: row.get(0)?,
+                value: row.get(1)?,
+            })
+        })?;
+
+        // TODO: print a warning for errors
+        Ok(settings.filter_map(|x| x.ok()).collect())
+    }
+    fn name(&self) -> &str {
+        self.key()
+    }
+}
+
+impl PrintableTableStruct for Setting {
+    fn get_header() -> Vec<Cell> {
+        vec![Cell::new("key"), Cell::new("value")]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![Cell::new(&self.key), Cell::new(&self.value)]]
+    }
+}
@@ -1,9 +1,20 @@
-use super::DbObject;
+use super::auth::MASK;
+use super::secret::{self, SecretKey};
 use super::PrintableTableStruct;
-use crate::error::Result;
-use chrono::Utc;
+use super::{DbObject, OAuth2};
+use crate::error::{Error, ErrorKind, Result};
+use chrono::{DateTime, NaiveDateTime, Utc};
 use comfy_table::Cell;
 use rusqlite::{params, Connection, NO_PARAMS};
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::process::{Command, ExitStatus, Stdio};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// How long a cmd: source may run before it is killed
+const CMD_TIMEOUT: Duration = Duration::from_secs(30);
 
 pub struct Variable {
     rowid: u32,
@@ -11,10 +22,21 @@ pub struct Variable {
     environment: String,
     value: Option<String>,
     source: Option<String>,
+    #[allow(dead_code)]
     timestamp: Option<String>,
+    expires: Option<String>,
+    // the value is encrypted
+    secret: bool,
 }
 
 impl Variable {
+    // Sources of variables defined by the user. Values starting with env:,
+    // file:, or cmd: are resolved every time the variable is used.
+    pub const USER: &'static str = "user";
+    pub const ENV: &'static str = "env";
+    pub const FILE: &'static str = "file";
+    pub const CMD: &'static str = "cmd";
+
     pub fn new(name: &str, env: &str, value: Option<&str>, source: Option<&str>) -> Variable {
         let value = value.map(|x| String::from(x));
         let source = source.map(|x| String::from(x));
@@ -25,6 +47,8 @@ impl Variable {
             value,
             source,
             timestamp: None,
+            expires: None,
+            secret: false,
         }
     }
     pub fn create_table(conn: &Connection) -> Result<()> {
@@ -36,10 +60,20 @@ impl Variable {
                   environment     TEXT NOT NULL,
                   value           TEXT,
                   source          TEXT,
-                  timestamp       TEXT
+                  timestamp       TEXT,
+                  expires         TEXT,
+                  secret          INTEGER NOT NULL DEFAULT 0
               )",
             NO_PARAMS,
         )?;
+        super::db::add_missing_columns(
+            conn,
+            "variables",
+            &[
+                ("expires", "TEXT"),
+                ("secret", "INTEGER NOT NULL DEFAULT 0"),
+            ],
+        )?;
         Ok(())
     }
 
@@ -55,25 +89,121 @@ impl Variable {
     pub fn source(&self) -> Option<&str> {
         self.source.as_deref()
     }
+    // The source of a value defined by the user
+    pub fn user_source(value: &str) -> &'static str {
+        for source in [Variable::ENV, Variable::FILE, Variable::CMD].iter() {
+            if value.starts_with(source) && value[source.len()..].starts_with(':') {
+                return source;
+            }
+        }
+        Variable::USER
+    }
+    pub fn is_user_defined(&self) -> bool {
+        match self.source() {
+            Some(x) => [Variable::USER, Variable::ENV, Variable::FILE, Variable::CMD].contains(&x),
+            None => false,
+        }
+    }
+    pub fn is_dynamic(&self) -> bool {
+        self.is_user_defined() && self.source() != Some(Variable::USER)
+    }
+    // Read the value from the environment, a file, or a command
+    pub fn resolve(&self) -> Result<String> {
+        let value = self.value().unwrap_or("");
+        let arg = value.split_once(':').map_or("", |x| x.1);
+        let failed = |reason: String| {
+            Error::new(ErrorKind::VariableSourceFailed(format!(
+                "{}: {}",
+                self.name, reason
+            )))
+        };
+        let resolved = match self.source() {
+            Some(Variable::ENV) => env::var(arg)
+                .map_err(|_| failed(format!("environment variable {} is not set", arg)))?,
+            Some(Variable::FILE) => {
+                fs::read_to_string(arg).map_err(|x| failed(format!("{}: {}", arg, x)))?
+            }
+            Some(Variable::CMD) => {
+                let (status, stdout, stderr) =
+                    run_command(arg, CMD_TIMEOUT).map_err(|x| failed(x.to_string()))?;
+                let status = match status {
+                    Some(x) => x,
+                    None => {
+                        return Err(failed(format!(
+                            "command timed out after {}s",
+                            CMD_TIMEOUT.as_secs()
+                        )))
+                    }
+                };
+                if !status.success() {
+                    let stderr = String::from_utf8_lossy(&stderr);
+                    return Err(failed(match stderr.trim() {
+                        "" => format!("command exited with {}", status),
+                        x => String::from(x),
+                    }));
+                }
+                String::from_utf8(stdout)
+                    .map_err(|_| failed(String::from("command output is not UTF-8")))?
+            }
+            _ => return Ok(String::from(value)),
+        };
+        // like shell substitution, drop the trailing newline
+        Ok(String::from(resolved.trim_end_matches(&['\r', '\n'][..])))
+    }
+    pub fn is_secret(&self) -> bool {
+        self.secret
+    }
+    pub fn set_secret(&mut self, secret: bool) {
+        self.secret = secret;
+    }
+    // Mark the variable as secret and encrypt its value
+    pub fn encrypt(&mut self, key: &SecretKey) {
+        if let Some(value) = self.value.as_ref().filter(|x| !secret::is_encrypted(x)) {
+            self.value = Some(key.encrypt(value));
+        }
+        self.secret = true;
+    }
+    // Replace the stored value with its plaintext, only for displaying it
+    pub fn decrypt(&mut self, key: &SecretKey) -> Result<()> {
+        if let Some(value) = self.value.as_ref().filter(|x| secret::is_encrypted(x)) {
+            self.value = Some(key.decrypt(value)?);
+        }
+        Ok(())
+    }
     pub fn set_value(&mut self, value: Option<&str>) {
         self.value = value.map(|x| String::from(x));
     }
     pub fn set_source(&mut self, source: Option<&str>) {
         self.source = source.map(|x| String::from(x));
     }
+    pub fn set_expires(&mut self, expires: Option<DateTime<Utc>>) {
+        self.expires = expires.map(|x| format!("{}", x.format("%Y-%m-%d %T %Z")));
+    }
+    // Variables without an expiry never expire
+    pub fn expired(&self) -> bool {
+        match &self.expires {
+            Some(x) => match NaiveDateTime::parse_from_str(x, "%Y-%m-%d %T UTC") {
+                Ok(expires) => expires.and_utc() <= Utc::now(),
+                Err(_) => true,
+            },
+            None => false,
+        }
+    }
 }
 
 impl DbObject for Variable {
     fn create(&self, conn: &Connection) -> Result<()> {
         conn.execute(
-            "INSERT INTO variables (name, environment, value, source, timestamp)
-                  VALUES (?1, ?2, ?3, ?4, ?5);",
+            "INSERT INTO variables (name, environment, value, source, timestamp, expires, secret)
+                  VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7);",
             params![
                 self.name,
                 self.environment,
                 self.value,
                 self.source,
-                format!("{}", Utc::now().format("%Y-%m-%d %T %Z"))
+                format!("{}", Utc::now().format("%Y-%m-%d %T %Z")),
+                self.expires,
+                self.secret,
             ],
         )?;
         Ok(())
@@ -93,14 +223,18 @@ impl DbObject for Variable {
                 environment = ?2,
                 value = ?3,
                 source = ?4,
-                timestamp = ?5
-            WHERE rowid = ?6;",
+                timestamp = ?5,
+                expires = ?6,
+                secret = ?7
+            WHERE rowid = ?8;",
             params![
                 self.name,
                 self.environment,
                 self.value,
                 self.source,
                 format!("{}", Utc::now().format("%Y-%m-%d %T %Z")),
+                self.expires,
+                self.secret,
                 self.rowid,
             ],
         )?;
@@ -110,7 +244,9 @@ impl DbObject for Variable {
                 "UPDATE variables SET
                     value = ?3,
                     source = ?4,
-                    timestamp = ?5
+                    timestamp = ?5,
+                    expires = ?6,
+                    secret = ?7
                 WHERE name = ?1 AND environment = ?2;",
                 params![
                     self.name,
@@ -118,6 +254,8 @@ impl DbObject for Variable {
                     self.value,
                     self.source,
                     format!("{}", Utc::now().format("%Y-%m-%d %T %Z")),
+                    self.expires,
+                    self.secret,
                 ],
             )?;
         }
@@ -125,7 +263,7 @@ impl DbObject for Variable {
     }
     fn get_all(conn: &Connection) -> Result<Vec<Variable>> {
         let mut stmt = conn.prepare(
-            "SELECT rowid, name, environment, value, source, timestamp
+            "SELECT rowid, name, environment, value, source, timestamp, expires, secret
                 FROM variables ORDER BY timestamp ASC;",
         )?;
 
@@ -137,6 +275,8 @@ impl DbObject for Variable {
                 value: row.get(3)?,
                 source: row.get(4)?,
                 timestamp: row.get(5)?,
+                expires: row.get(6)?,
+                secret: row.get(7)?,
             })
         })?;
 
@@ -159,12 +299,115 @@ impl PrintableTableStruct for Variable {
         ]
     }
     fn get_rows(&self) -> Vec<Vec<Cell>> {
+        // cached OAuth2 tokens are live credentials
+        let value = match self.source() {
+            Some(OAuth2::SOURCE) => MASK,
+            _ => secret::mask(self.value().unwrap_or("")),
+        };
         vec![vec![
             Cell::new(self.rowid),
             Cell::new(self.name()),
             Cell::new(self.environment()),
-            Cell::new(self.value.as_ref().unwrap_or(&String::from(""))),
+            Cell::new(value),
             Cell::new(self.source.as_ref().unwrap_or(&String::from(""))),
         ]]
     }
 }
+
+// Run a shell command, killing it after the timeout. Returns the exit status,
+// or None if the command timed out, with its stdout and stderr.
+fn run_command(
+    command: &str,
+    timeout: Duration,
+) -> std::io::Result<(Option<ExitStatus>, Vec<u8>, Vec<u8>)> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    // read the output while waiting so the command cannot block on a full pipe
+    let read = |pipe: Option<Box<dyn Read + Send>>| {
+        thread::spawn(move || {
+            let mut buf = vec![];
+            if let Some(mut pipe) = pipe {
+                pipe.read_to_end(&mut buf).ok();
+            }
+            buf
+        })
+    };
+    let stdout = read(child.stdout.take().map(|x| Box::new(x) as _));
+    let stderr = read(child.stderr.take().map(|x| Box::new(x) as _));
+
+    let deadline = Instant::now() + timeout;
+    loop {
+        if let Some(status) = child.try_wait()? {
+            return Ok((
+                Some(status),
+                stdout.join().unwrap_or_default(),
+                stderr.join().unwrap_or_default(),
+            ));
+        }
+        if Instant::now() >= deadline {
+            // processes started by the command may still hold the pipes, so
+            // the output is not waited for
+            child.kill().ok();
+            child.wait()?;
+            return Ok((None, vec![], vec![]));
+        }
+        thread::sleep(Duration::from_millis(10));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn resolve(value: &str) -> Result<String> {
+        let source = Variable::user_source(value);
+        Variable::new("x", "local", Some(value), Some(source)).resolve()
+    }
+
+    #[test]
+    fn user_sources() {
+        assert_eq!(Variable::user_source("env:HOME"), Variable::ENV);
+        assert_eq!(Variable::user_source("file:/tmp/x"), Variable::FILE);
+        assert_eq!(Variable::user_source("cmd:echo hi"), Variable::CMD);
+        assert_eq!(Variable::user_source("environment"), Variable::USER);
+        assert_eq!(Variable::user_source("cmd"), Variable::USER);
+        assert_eq!(Variable::user_source("http://localhost"), Variable::USER);
+        assert_eq!(Variable::user_source(" env:HOME"), Variable::USER);
+    }
+
+    #[test]
+    fn resolve_sources() {
+        let dir = TempDir::new().unwrap();
+        let path = dir.path().join("token");
+        fs::write(&path, "from-file\n").unwrap();
+        env::set_var("REPOST_TEST_VARIABLE", "from-env");
+
+        assert_eq!(resolve("plain").unwrap(), "plain");
+        assert_eq!(resolve("env:REPOST_TEST_VARIABLE").unwrap(), "from-env");
+        assert_eq!(
+            resolve(&format!("file:{}", path.display())).unwrap(),
+            "from-file"
+        );
+        assert_eq!(resolve("cmd:echo from-cmd").unwrap(), "from-cmd");
+        assert!(resolve("env:REPOST_TEST_UNSET_VARIABLE").is_err());
+        assert!(resolve("cmd:echo failed >&2; exit 3").is_err());
+    }
+
+    #[test]
+    fn command_timeout() {
+        let start = Instant::now();
+        let (status, _, _) = run_command("sleep 5", Duration::from_millis(100)).unwrap();
+        assert!(status.is_none());
+        assert!(start.elapsed() < Duration::from_secs(5));
+
+        let (status, stdout, _) = run_command("echo hi", Duration::from_secs(5)).unwrap();
+        assert!(status.unwrap().success());
+        assert_eq!(stdout, b"hi\n");
+    }
+}
@@ -0,0 +1,194 @@
+use super::{DbObject, PrintableTableStruct};
+use crate::error::{Error, ErrorKind, Result};
+use comfy_table::Cell;
+use rusqlite::{params, Connection, NO_PARAMS};
+
+// A single step of a named workflow. Steps are run in order of their index.
+#[derive(Clone)]
+pub struct WorkflowStep {
+    workflow_name: String,
+    step: u32,
+    request_name: String,
+    options: Vec<(String, String)>,
+}
+
+impl WorkflowStep {
+    pub fn new(name: &str, step: u32, req_name: &str, options: Vec<(&str, &str)>) -> WorkflowStep {
+        WorkflowStep {
+            workflow_name: String::from(name),
+            step,
+            request_name: String::from(req_name),
+            options: options
+                .into_iter()
+                .map(|(k, v)| (String::from(k), String::from(v)))
+                .collect(),
+        }
+    }
+    // Parse a step of the form `request option=value ...`
+    pub fn parse(name: &str, step: u32, s: &str) -> Result<WorkflowStep> {
+        let tokens = shlex::split(s).unwrap_or_default();
+        if tokens.is_empty() {
+            return Err(Error::new(ErrorKind::ArgumentError("Found an empty step.")));
+        }
+        if !tokens[1..].iter().all(|s| s.contains('=')) {
+            return Err(Error::new(ErrorKind::ArgumentError(
+                "Step options must be in the form option=value.",
+            )));
+        }
+        let options = tokens[1..]
+            .iter()
+            .map(|s| {
+                let mut items = s.splitn(2, '=');
+                // We can unwrap because this argument is guaranteed to have one '='
+                (items.next().unwrap(), items.next().unwrap())
+            })
+            .collect();
+        Ok(WorkflowStep::new(name, step, &tokens[0], options))
+    }
+    pub fn create_table(conn: &Connection) -> Result<()> {
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS workflows (
+                  workflow_name     TEXT NOT NULL,
+                  step              INTEGER NOT NULL,
+                  request_name      TEXT NOT NULL,
+                  options           TEXT,
+                  UNIQUE(workflow_name, step)
+              )",
+            NO_PARAMS,
+        )?;
+        Ok(())
+    }
+
+    pub fn workflow_name(&self) -> &str {
+        self.workflow_name.as_ref()
+    }
+    pub fn step(&self) -> u32 {
+        self.step
+    }
+    pub fn request_name(&self) -> &str {
+        self.request_name.as_ref()
+    }
+    // Option overrides grouped by option name, in the order they were given
+    pub fn options(&self) -> Vec<(&str, Vec<&str>)> {
+        let mut options: Vec<(&str, Vec<&str>)> = vec![];
+        for (name, value) in self.options.iter() {
+            match options.iter_mut().find(|x| x.0 == name) {
+                Some(x) => x.1.push(value),
+                None => options.push((name, vec![value])),
+            }
+        }
+        options
+    }
+
+    // The inverse of parse
+    pub fn to_step_string(&self) -> String {
+        let mut tokens = vec![shlex::quote(&self.request_name).into_owned()];
+        for (name, value) in self.options.iter() {
+            tokens.push(shlex::quote(&format!("{}={}", name, value)).into_owned());
+        }
+        tokens.join(" ")
+    }
+
+    fn stringify_options(&self) -> Option<String> {
+        match self.options.len() {
+            0 => None,
+            _ => Some(
+                self.options
+                    .iter()
+                    .map(|x| format!("{}={}", x.0, x.1))
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+    fn unstringify_options(v: Option<String>) -> Vec<(String, String)> {
+        match v {
+            Some(v) => v
+                .split('\n')
+                .filter_map(|x| {
+                    let mut items = x.splitn(2, '=');
+                    Some((String::from(items.next()?), String::from(items.next()?)))
+                })
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+impl DbObject for WorkflowStep {
+    fn create(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "INSERT INTO workflows
+                (workflow_name, step, request_name, options)
+                VALUES (?1, ?2, ?3, ?4);",
+            params![
+                self.workflow_name,
+                self.step,
+                self.request_name,
+                self.stringify_options(),
+            ],
+        )?;
+        Ok(())
+    }
+    fn delete(&self, conn: &Connection) -> Result<()> {
+        conn.execute(
+            "DELETE FROM workflows WHERE workflow_name = ?1 AND step = ?2;",
+            params![self.workflow_name, self.step],
+        )?;
+        Ok(())
+    }
+    fn update(&self, conn: &Connection) -> Result<usize> {
+        let num = conn.execute(
+            "UPDATE workflows SET request_name = ?1, options = ?2
+                WHERE workflow_name = ?3 AND step = ?4;",
+            params![
+                self.request_name,
+                self.stringify_options(),
+                self.workflow_name,
+                self.step
+            ],
+        )?;
+        Ok(num)
+    }
+    fn get_all(conn: &Connection) -> Result<Vec<WorkflowStep>> {
+        let mut stmt = conn.prepare(
+            "SELECT
+                workflow_name, step, request_name, options
+            FROM workflows ORDER BY workflow_name, step;",
+        )?;
+
+        let steps = stmt.query_map(NO_PARAMS, |row| {
+            Ok(WorkflowStep {
+                workflow_name: row.get(0)?,
+                step: row.get(1)?,
+                request_name: row.get(2)?,
+                options: WorkflowStep::unstringify_options(row.get(3)?),
+            })
+        })?;
+
+        // TODO: print a warning for errors
+        Ok(steps.filter_map(|step| step.ok()).collect())
+    }
+    fn name(&self) -> &str {
+        self.workflow_name()
+    }
+}
+
+impl PrintableTableStruct for WorkflowStep {
+    fn get_header() -> Vec<Cell> {
+        vec![
+            Cell::new("workflow"),
+            Cell::new("step"),
+            Cell::new("request"),
+            Cell::new("options"),
+        ]
+    }
+    fn get_rows(&self) -> Vec<Vec<Cell>> {
+        vec![vec![
+            Cell::new(&self.workflow_name),
+            Cell::new(self.step),
+            Cell::new(&self.request_name),
+            Cell::new(self.stringify_options().unwrap_or_default()),
+        ]]
+    }
+}
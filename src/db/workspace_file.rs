@@ -0,0 +1,471 @@
+use super::secret;
+use super::{
+    Assertion, Auth, Config, DbObject, OAuth2, OutputOption, Request, Variable, WorkflowStep,
+};
+use crate::error::{Error, ErrorKind, Result};
+use reqwest::Method;
+use rusqlite::Connection;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// A human readable representation of a workspace. Everything is sorted by
+// name so the output is stable and easy to diff.
+#[derive(Serialize, Deserialize, Default)]
+pub struct WorkspaceFile {
+    #[serde(default)]
+    requests: Vec<RequestEntry>,
+    // variable name -> environment -> value
+    #[serde(default)]
+    variables: BTreeMap<String, BTreeMap<String, String>>,
+    // workflow name -> steps
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    workflows: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    config: Vec<ConfigEntry>,
+    // environment -> OAuth2 configuration
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    oauth2: BTreeMap<String, OAuth2Entry>,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct RequestEntry {
+    name: String,
+    method: String,
+    url: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    headers: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    body: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    combination: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    auth: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    input_options: BTreeMap<String, Vec<String>>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    output_options: Vec<OutputEntry>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    assertions: Vec<AssertionEntry>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct OutputEntry {
+    variable: String,
+    #[serde(rename = "type")]
+    extraction_type: String,
+    source: String,
+}
+
+#[derive(Serialize, Deserialize)]
+struct AssertionEntry {
+    #[serde(rename = "type")]
+    assertion_type: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    source: String,
+    comparison: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    expected: Option<String>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct ConfigEntry {
+    key: String,
+    value: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    environment: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    request: Option<String>,
+}
+
+// Credentials are variable names, as in the database
+#[derive(Serialize, Deserialize)]
+struct OAuth2Entry {
+    token_url: String,
+    grant_type: String,
+    client_id: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    client_secret: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    scopes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    username: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    password: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    refresh_token: Option<String>,
+}
+
+impl WorkspaceFile {
+    pub fn from_db(conn: &Connection) -> Result<WorkspaceFile> {
+        let mut requests: Vec<RequestEntry> = Request::get_all(conn)?
+            .iter()
+            .map(RequestEntry::from)
+            .collect();
+        requests.sort_by(|a, b| a.name.cmp(&b.name));
+        for req in requests.iter_mut() {
+            req.output_options
+                .sort_by(|a, b| a.variable.cmp(&b.variable));
+            req.set_assertions(&Assertion::get_by_name(conn, &req.name)?);
+            // option values that come from the variables of any environment
+            // are left out, so the file does not depend on the environment
+            // the options were last populated from
+            for (name, values) in req.input_options.iter_mut() {
+                let mut environments: BTreeMap<String, Vec<String>> = BTreeMap::new();
+                for var in Variable::get_by(conn, |x| x.name() == name)? {
+                    if let Some(value) = var.value() {
+                        environments
+                            .entry(String::from(var.environment()))
+                            .or_default()
+                            .push(String::from(value));
+                    }
+                }
+                if environments.values().any(|x| x == values) {
+                    values.clear();
+                }
+            }
+            req.input_options.retain(|_, values| !values.is_empty());
+        }
+
+        // only variables defined by the user are part of the workspace, and
+        // secret values never leave the database
+        let mut variables: BTreeMap<String, BTreeMap<String, String>> = BTreeMap::new();
+        for var in Variable::get_by(conn, |x| x.is_user_defined() && !x.is_secret())? {
+            variables
+                .entry(String::from(var.name()))
+                .or_default()
+                .insert(
+                    String::from(var.environment()),
+                    String::from(var.value().unwrap_or("")),
+                );
+        }
+        let mut workflows: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for step in WorkflowStep::get_all(conn)? {
+            workflows
+                .entry(String::from(step.workflow_name()))
+                .or_default()
+                .push(step.to_step_string());
+        }
+        // the client certificate password never leaves the database
+        let config = Config::get_all(conn)?
+            .iter()
+            .filter(|x| x.key() != Config::CLIENT_CERT_PASSWORD)
+            .map(|x| ConfigEntry {
+                key: String::from(x.key()),
+                value: String::from(x.value()),
+                environment: x.environment().map(String::from),
+                request: x.request_name().map(String::from),
+            })
+            .collect();
+        let oauth2 = OAuth2::get_all(conn)?
+            .iter()
+            .map(|x| {
+                let entry = OAuth2Entry {
+                    token_url: String::from(x.token_url()),
+                    grant_type: String::from(x.grant_type()),
+                    client_id: String::from(x.client_id()),
+                    client_secret: x.client_secret().map(String::from),
+                    scopes: x.scopes().map(String::from),
+                    username: x.username().map(String::from),
+                    password: x.password().map(String::from),
+                    refresh_token: x.refresh_token().map(String::from),
+                };
+                (String::from(x.environment()), entry)
+            })
+            .collect();
+        Ok(WorkspaceFile {
+            requests,
+            variables,
+            workflows,
+            config,
+            oauth2,
+        })
+    }
+
+    // Variables whose value is read from the environment, a file, or a
+    // command when the file is imported with sources allowed
+    pub fn sourced_variables(&self) -> Vec<(&str, &str, &str)> {
+        let mut sourced = vec![];
+        for (name, values) in self.variables.iter() {
+            for (environment, value) in values.iter() {
+                if Variable::user_source(value) != Variable::USER {
+                    sourced.push((name.as_str(), environment.as_str(), value.as_str()));
+                }
+            }
+        }
+        sourced
+    }
+
+    // Values are stored as they are unless allow_sources is set, so a shared
+    // file cannot run commands or read files without the user opting in
+    pub fn to_db(&self, conn: &Connection, replace: bool, allow_sources: bool) -> Result<()> {
+        // nothing is changed if any part of the file fails to import
+        let tx = conn.unchecked_transaction()?;
+        self.write(&tx, replace, allow_sources)?;
+        tx.commit()?;
+        Ok(())
+    }
+
+    fn write(&self, conn: &Connection, replace: bool, allow_sources: bool) -> Result<()> {
+        if replace {
+            for config in Config::get_all(conn)? {
+                config.delete(conn)?;
+            }
+            for oauth2 in OAuth2::get_all(conn)? {
+                oauth2.delete(conn)?;
+            }
+            for var in Variable::get_by(conn, |x| x.source() == Some(OAuth2::SOURCE))? {
+                var.delete(conn)?;
+            }
+            for req in Request::get_all(conn)? {
+                req.delete(conn)?;
+            }
+            for var in Variable::get_by(conn, |x| x.is_user_defined())? {
+                var.delete(conn)?;
+            }
+            for step in WorkflowStep::get_all(conn)? {
+                step.delete(conn)?;
+            }
+        }
+
+        for entry in self.requests.iter() {
+            // requests in the file overwrite existing requests of the same name
+            for req in Request::get_by_name(conn, &entry.name)? {
+                req.delete(conn)?;
+            }
+            let req = entry.to_request()?;
+            req.create(conn)?;
+            for opt in entry.output_options(&entry.name) {
+                opt.create(conn)?;
+            }
+            for assertion in entry.assertions(&entry.name) {
+                assertion.create(conn)?;
+            }
+        }
+
+        for (name, values) in self.variables.iter() {
+            for (environment, value) in values.iter() {
+                let mut var =
+                    Variable::get_by(conn, |x| x.name() == name && x.environment() == environment)?;
+                let source = match allow_sources {
+                    true => Variable::user_source(value),
+                    false => Variable::USER,
+                };
+                let mut var = match var.len() {
+                    0 => Variable::new(name, environment, Some(value), Some(source)),
+                    _ => var.remove(0),
+                };
+                if var.is_user_defined() {
+                    var.set_source(Some(source));
+                }
+                var.set_secret(false);
+                var.set_value(Some(value));
+                var.upsert(conn)?;
+            }
+        }
+
+        for (name, steps) in self.workflows.iter() {
+            // workflows in the file overwrite existing workflows of the same name
+            for step in WorkflowStep::get_by_name(conn, name)? {
+                step.delete(conn)?;
+            }
+            for (i, step) in steps.iter().enumerate() {
+                WorkflowStep::parse(name, i as u32 + 1, step)?.create(conn)?;
+            }
+        }
+
+        for entry in self.config.iter() {
+            Config::new(
+                &entry.key,
+                &entry.value,
+                entry.environment.as_deref(),
+                entry.request.as_deref(),
+            )
+            .upsert(conn)?;
+        }
+
+        for (environment, entry) in self.oauth2.iter() {
+            let valid = match entry.grant_type.as_str() {
+                OAuth2::CLIENT_CREDENTIALS => true,
+                OAuth2::PASSWORD => entry.username.is_some() && entry.password.is_some(),
+                OAuth2::REFRESH_TOKEN => entry.refresh_token.is_some(),
+                _ => false,
+            };
+            if !valid {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Found invalid OAuth2 configuration.",
+                )));
+            }
+            let mut oauth2 = OAuth2::new(
+                environment,
+                &entry.token_url,
+                &entry.grant_type,
+                &entry.client_id,
+            );
+            oauth2.set_client_secret(entry.client_secret.as_deref());
+            oauth2.set_scopes(entry.scopes.as_deref());
+            oauth2.set_username(entry.username.as_deref());
+            oauth2.set_password(entry.password.as_deref());
+            oauth2.set_refresh_token(entry.refresh_token.as_deref());
+            oauth2.upsert(conn)?;
+            // tokens from the previous configuration are no longer valid
+            for var in Variable::get_by(conn, |x| {
+                x.environment() == environment && x.source() == Some(OAuth2::SOURCE)
+            })? {
+                var.delete(conn)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl RequestEntry {
+    pub fn name(&self) -> &str {
+        self.name.as_ref()
+    }
+    pub fn to_request(&self) -> Result<Request> {
+        let method = Method::from_bytes(self.method.as_bytes())
+            .map_err(|_| Error::new(ErrorKind::ArgumentError("Invalid HTTP method.")))?;
+        let mut req = Request::new(&self.name, Some(method), &self.url);
+        for header in self.headers.iter() {
+            let mut items = header.splitn(2, ':');
+            let (key, value) = (items.next(), items.next());
+            if key.and(value).is_none() {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Found header that does not contain ':'",
+                )));
+            }
+            req.add_header(key.unwrap().trim(), value.unwrap().trim());
+        }
+        req.set_body(self.body.as_ref().map(|x| x.as_bytes().to_vec()));
+        req.set_combination(self.combination.as_deref());
+        if let Some(auth) = &self.auth {
+            req.set_auth(Some(Auth::parse(auth).map_err(|_| {
+                Error::new(ErrorKind::ArgumentError("Found invalid auth."))
+            })?));
+        }
+        for (name, values) in self.input_options.iter() {
+            // options that are no longer used by the request are ignored
+            let _ = req.set_input_option(name, values.iter().map(AsRef::as_ref).collect());
+        }
+        Ok(req)
+    }
+
+    pub fn output_options(&self, req_name: &str) -> Vec<OutputOption> {
+        self.output_options
+            .iter()
+            .map(|x| OutputOption::new(req_name, &x.variable, &x.extraction_type, &x.source))
+            .collect()
+    }
+
+    pub fn assertions(&self, req_name: &str) -> Vec<Assertion> {
+        self.assertions
+            .iter()
+            .map(|x| {
+                Assertion::new(
+                    req_name,
+                    &x.assertion_type,
+                    &x.source,
+                    &x.comparison,
+                    x.expected.as_deref(),
+                )
+            })
+            .collect()
+    }
+    pub fn set_assertions(&mut self, assertions: &[Assertion]) {
+        self.assertions = assertions
+            .iter()
+            .map(|x| AssertionEntry {
+                assertion_type: String::from(x.assertion_type()),
+                source: String::from(x.source()),
+                comparison: String::from(x.comparison()),
+                expected: x.expected().map(String::from),
+            })
+            .collect();
+    }
+}
+
+impl From<&Request> for RequestEntry {
+    fn from(req: &Request) -> RequestEntry {
+        RequestEntry {
+            name: String::from(req.name()),
+            method: String::from(req.method().as_str()),
+            url: String::from(req.url()),
+            headers: match req.headers() {
+                Some(headers) => headers.split('\n').map(String::from).collect(),
+                None => vec![],
+            },
+            body: req
+                .body()
+                .as_ref()
+                .map(|x| String::from_utf8_lossy(x).into_owned()),
+            combination: match req.combination() {
+                "zip" => None,
+                x => Some(String::from(x)),
+            },
+            auth: req.auth().map(Auth::to_auth_string),
+            input_options: req
+                .input_options()
+                .iter()
+                .map(|x| {
+                    (
+                        String::from(x.option_name()),
+                        // secret values never leave the database
+                        x.values()
+                            .into_iter()
+                            .filter(|v| !secret::is_encrypted(v))
+                            .map(String::from)
+                            .collect(),
+                    )
+                })
+                .collect(),
+            output_options: req
+                .output_options()
+                .iter()
+                .map(|x| OutputEntry {
+                    variable: String::from(x.option_name()),
+                    extraction_type: String::from(x.extraction_type()),
+                    source: String::from(x.extraction_source()),
+                })
+                .collect(),
+            assertions: vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::Db;
+    use tempfile::TempDir;
+
+    #[test]
+    fn failed_import_changes_nothing() {
+        let dir = TempDir::new().unwrap();
+        let db = Db::new(dir.path(), "test.db").unwrap();
+        let conn = db.conn();
+        Request::new("get-old", None, "http://localhost/old")
+            .create(conn)
+            .unwrap();
+
+        let file: WorkspaceFile = serde_yaml::from_str(
+            "requests:
+  - name: get-new
+    method: GET
+    url: http://localhost/new
+  - name: get-bad
+    method: GET
+    url: http://localhost/bad
+    headers: [no-colon]
+",
+        )
+        .unwrap();
+        assert!(file.to_db(conn, true, false).is_err());
+
+        let names: Vec<String> = Request::get_all(conn)
+            .unwrap()
+            .iter()
+            .map(|x| String::from(x.name()))
+            .collect();
+        assert_eq!(names, vec!["get-old"]);
+    }
+}
@@ -10,6 +10,14 @@ pub enum ErrorKind {
     ArgumentError(&'static str),
     RequestStateExpected(&'static str),
     MissingOptions(Vec<String>),
+    AssertionsFailed(usize),
+    WorkflowFailed(String),
+    // a request that could not be sent, with secret values masked
+    RequestFailed(String),
+    TokenRequestFailed(String),
+    VariableSourceFailed(String),
+    InvalidPlaceholder(String),
+    DependencyCycle(Vec<String>),
     ReqwestError(reqwest::Error),
     ParseError,
     NotFound,
@@ -36,6 +44,18 @@ impl std::fmt::Display for Error {
             ErrorKind::MissingOptions(x) => {
                 write!(f, "The following options are missing: {}", x.join(", "))
             }
+            ErrorKind::AssertionsFailed(x) => match x {
+                1 => write!(f, "1 assertion failed."),
+                _ => write!(f, "{} assertions failed.", x),
+            },
+            ErrorKind::WorkflowFailed(x) => write!(f, "Workflow stopped: {}.", x),
+            ErrorKind::RequestFailed(x) => write!(f, "{}", x),
+            ErrorKind::TokenRequestFailed(x) => write!(f, "Could not get an access token: {}.", x),
+            ErrorKind::VariableSourceFailed(x) => write!(f, "Could not resolve variable {}.", x),
+            ErrorKind::InvalidPlaceholder(x) => write!(f, "Invalid placeholder: {}", x),
+            ErrorKind::DependencyCycle(x) => {
+                write!(f, "Found a dependency cycle: {}", x.join(" -> "))
+            }
             ErrorKind::ReqwestError(x) => write!(f, "{}", x),
             ErrorKind::NotFound => write!(f, "Not found."),
             ErrorKind::ParseError => write!(f, "Parse error."),
@@ -51,6 +71,13 @@ impl std::fmt::Debug for Error {
             ErrorKind::ArgumentError(x) => write!(f, "ArgumentError({})", x),
             ErrorKind::RequestStateExpected(x) => write!(f, "RequestStateExpected({})", x),
             ErrorKind::MissingOptions(x) => write!(f, "MissingOptions({:?})", x),
+            ErrorKind::AssertionsFailed(x) => write!(f, "AssertionsFailed({})", x),
+            ErrorKind::WorkflowFailed(x) => write!(f, "WorkflowFailed({})", x),
+            ErrorKind::RequestFailed(x) => write!(f, "RequestFailed({})", x),
+            ErrorKind::TokenRequestFailed(x) => write!(f, "TokenRequestFailed({})", x),
+            ErrorKind::VariableSourceFailed(x) => write!(f, "VariableSourceFailed({})", x),
+            ErrorKind::InvalidPlaceholder(x) => write!(f, "InvalidPlaceholder({})", x),
+            ErrorKind::DependencyCycle(x) => write!(f, "DependencyCycle({:?})", x),
             ErrorKind::ReqwestError(x) => write!(f, "ReqwestError({})", x),
             ErrorKind::NotFound => write!(f, "Not found."),
             ErrorKind::ParseError => write!(f, "Parse error."),
@@ -93,6 +120,13 @@ impl From<serde_json::Error> for Error {
         }
     }
 }
+impl From<serde_yaml::Error> for Error {
+    fn from(_err: serde_yaml::Error) -> Error {
+        Error {
+            kind: ErrorKind::ParseError,
+        }
+    }
+}
 impl From<regex::Error> for Error {
     fn from(_err: regex::Error) -> Error {
         Error {
@@ -4,7 +4,7 @@ mod db;
 pub mod error;
 
 use bastion::Bastion;
-use error::Result;
+use error::{Error, ErrorKind, Result};
 
 use std::env;
 use std::fs;
@@ -16,10 +16,15 @@ pub struct Repl {
 }
 
 impl Repl {
-    pub fn new() -> Result<Repl> {
+    pub fn new(interactive: bool) -> Result<Repl> {
         let base_dir = env::var("XDG_CONFIG_DIR");
         let home_dir = env::var("HOME");
         let root = match (base_dir, home_dir) {
+            (Err(_), Err(_)) if !interactive => {
+                return Err(Error::new(ErrorKind::ArgumentError(
+                    "Could not find a viable location for repost's files.",
+                )));
+            }
             (Err(_), Err(_)) => {
                 // ask the user where to create the files
                 let mut s = String::new();
@@ -32,6 +37,9 @@ impl Repl {
             (Ok(conf), _) => Path::new(&conf).join("repost"),
             (_, Ok(home)) => Path::new(&home).join(".repost"),
         };
+        if !root.exists() && !interactive {
+            fs::create_dir_all(&root)?;
+        }
         if !root.exists() {
             let mut s = String::new();
             if get_input(
@@ -57,7 +65,7 @@ impl Repl {
         }
 
         let repl = Repl {
-            bastion: Bastion::new(root)?,
+            bastion: Bastion::new(root, interactive)?,
         };
         Ok(repl)
     }
@@ -69,6 +77,27 @@ impl Repl {
     pub fn execute(&mut self, command: &str) -> Result<()> {
         self.bastion.execute(command)
     }
+
+    pub fn execute_args(&mut self, args: Vec<&str>) -> Result<()> {
+        self.bastion.execute_args(args)
+    }
+
+    pub fn set_workspace(&mut self, workspace: &str) -> Result<()> {
+        self.execute_args(vec!["set", "workspace", workspace])
+    }
+
+    pub fn set_environment(&mut self, environment: &str) -> Result<()> {
+        self.execute_args(vec!["set", "environment", environment])
+    }
+
+    pub fn set_request(&mut self, request: &str) -> Result<()> {
+        self.execute_args(vec!["set", "request", request])
+    }
+
+    pub fn set_key_file(&mut self, key_file: &str) {
+        self.bastion
+            .set_key_file(Some(Path::new(key_file).to_path_buf()));
+    }
 }
 
 fn get_input(prompt: &str, mut input: &mut String) -> Option<()> {
@@ -1,12 +1,46 @@
+use clap_v3::{load_yaml, App};
 use repost::error::Error;
 use repost::Repl;
+use std::fs;
+use std::path::Path;
 
 fn main() -> Result<(), Error> {
-    let mut input = String::new();
-    let mut repl = Repl::new()?;
+    let cli_yaml = load_yaml!("cli.yml");
+    let matches = App::from(cli_yaml).get_matches();
+    let command: Vec<&str> = matches.values_of("command").unwrap_or_default().collect();
+    let interactive = command.is_empty();
+
+    let mut repl = Repl::new(interactive)?;
+    if let Some(key_file) = matches.value_of("key-file") {
+        repl.set_key_file(key_file);
+    }
+    if let Some(ws) = matches.value_of("workspace") {
+        repl.set_workspace(ws)?;
+    }
+    if let Some(env) = matches.value_of("environment") {
+        repl.set_environment(env)?;
+    }
+    if let Some(req) = matches.value_of("request") {
+        repl.set_request(req)?;
+    }
+
+    if !interactive {
+        // a single argument naming a file is a script, otherwise a command
+        let ok = if command.len() == 1 && Path::new(command[0]).is_file() {
+            let keep_going = matches.is_present("continue-on-error");
+            run_script(&mut repl, command[0], keep_going)?
+        } else {
+            run_command(&mut repl, command)
+        };
+        if !ok {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
 
+    let mut input = String::new();
     loop {
-        if repl.get_input(&mut input) == None {
+        if repl.get_input(&mut input).is_none() {
             break;
         }
 
@@ -17,3 +51,30 @@ fn main() -> Result<(), Error> {
 
     Ok(())
 }
+
+fn run_command(repl: &mut Repl, command: Vec<&str>) -> bool {
+    if let Err(x) = repl.execute_args(command) {
+        eprintln!("[!] {}", x);
+        return false;
+    }
+    true
+}
+
+fn run_script(repl: &mut Repl, filename: &str, keep_going: bool) -> Result<bool, Error> {
+    let mut ok = true;
+    for (num, line) in fs::read_to_string(filename)?.lines().enumerate() {
+        let line = line.trim();
+        // skip empty lines and comments
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if let Err(x) = repl.execute(line) {
+            eprintln!("[!] {}:{}: {}", filename, num + 1, x);
+            ok = false;
+            if !keep_going {
+                break;
+            }
+        }
+    }
+    Ok(ok)
+}